@@ -38,7 +38,7 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
     builder.add_operation(create_account_op);
 
     let mut tx = builder.build();
-    tx.sign(&[source_keypair.clone()]);
+    tx.sign(std::slice::from_ref(&source_keypair));
 
     let response = server.send_transaction(tx).await?;
 
@@ -62,7 +62,7 @@ pub async fn main() -> Result<(), Box<dyn std::error::Error>> {
     builder.add_operation(payment);
 
     let mut tx = builder.build();
-    tx.sign(&[source_keypair.clone()]);
+    tx.sign(std::slice::from_ref(&source_keypair));
 
     let response = server.send_transaction(tx).await?;
 
@@ -0,0 +1,122 @@
+//! Derive macros of the [soroban-client](https://docs.rs/soroban-client) crate
+//!
+//! Use them through the re-exports of `soroban-client`, such as
+//! `soroban_client::events::FromContractEvent`.
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Fields, LitInt, LitStr};
+
+/// Derive `FromContractEvent`, to decode a contract event into a struct
+///
+/// The struct attribute `#[event(name = "...")]` requires the first topic to be this symbol.
+/// Each field takes one of these attributes:
+/// - `#[event(topic = N)]`: the topic at index `N`
+/// - `#[event(value)]`: the value of the event
+/// - `#[event(value = "key")]`: the entry `key` of the value, when it is a map
+///
+/// The type of each field implements `FromScVal`.
+#[proc_macro_derive(FromContractEvent, attributes(event))]
+pub fn derive_from_contract_event(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+const MISSING_SOURCE: &str =
+    "expected #[event(topic = N)], #[event(value)] or #[event(value = \"key\")]";
+
+enum Source {
+    Topic(LitInt),
+    Value,
+    ValueEntry(LitStr),
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let ident = &input.ident;
+    let target = ident.to_string();
+
+    let mut name = None;
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("event")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?);
+                Ok(())
+            } else {
+                Err(meta.error("expected `name = \"...\"`"))
+            }
+        })?;
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new_spanned(
+                    ident,
+                    "FromContractEvent can only be derived for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "FromContractEvent can only be derived for structs",
+            ))
+        }
+    };
+
+    let mut initializers = Vec::new();
+    for field in fields {
+        let mut source = None;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("event")) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path.is_ident("topic") {
+                    source = Some(Source::Topic(meta.value()?.parse()?));
+                    Ok(())
+                } else if meta.path.is_ident("value") {
+                    source = Some(match meta.input.peek(syn::Token![=]) {
+                        true => Source::ValueEntry(meta.value()?.parse()?),
+                        false => Source::Value,
+                    });
+                    Ok(())
+                } else {
+                    Err(meta.error("expected `topic = N`, `value` or `value = \"key\"`"))
+                }
+            })?;
+        }
+
+        let field_ident = field.ident.as_ref().expect("named field");
+        let Some(source) = source else {
+            return Err(syn::Error::new_spanned(field, MISSING_SOURCE));
+        };
+        let decode = match source {
+            Source::Topic(index) => quote!(decoder.topic(#index)?),
+            Source::Value => quote!(decoder.value()?),
+            Source::ValueEntry(key) => quote!(decoder.value_entry(#key)?),
+        };
+        initializers.push(quote!(#field_ident: #decode));
+    }
+
+    let name_check = name.map(|name| quote!(decoder.name(#name)?;));
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::soroban_client::events::FromContractEvent
+            for #ident #ty_generics #where_clause
+        {
+            fn from_contract_event(
+                topics: &[::soroban_client::xdr::ScVal],
+                value: &::soroban_client::xdr::ScVal,
+            ) -> ::core::result::Result<Self, ::soroban_client::error::Error> {
+                let decoder = ::soroban_client::events::EventDecoder::new(#target, topics, value);
+                #name_check
+                ::core::result::Result::Ok(Self {
+                    #(#initializers),*
+                })
+            }
+        }
+    })
+}
@@ -0,0 +1,245 @@
+use std::marker::PhantomData;
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use stellar_baselib::transaction::Transaction;
+use stellar_baselib::xdr::LedgerKey;
+use tracing::Instrument;
+
+use crate::error::Error;
+use crate::server::{
+    events_params, handle_response, ledger_entries_params, paginated_params,
+    send_transaction_params, simulate_transaction_params, validate_event_filters, EventFilter,
+    Pagination, SimulationOptions,
+};
+use crate::soroban_rpc::*;
+use crate::telemetry;
+use crate::time::Instant;
+use crate::transport::RpcTransport;
+
+/// Handle to the result of a call queued in a [Batch]
+///
+/// Use it with [BatchResponse::take] once the batch is sent.
+#[derive(Debug)]
+pub struct BatchCall<T> {
+    index: usize,
+    _response: PhantomData<fn() -> T>,
+}
+
+/// Builder of a [JSON-RPC batch], created with [Server::batch](crate::Server::batch)
+///
+/// Calls are queued and sent together in a single HTTP request with [Batch::send]. Each queued
+/// call returns a [BatchCall] handle to retrieve its own result.
+///
+/// # Example
+/// ```rust
+/// # use soroban_client::*;
+/// # use soroban_client::error::Error;
+/// # async fn batch() -> Result<(), Error> {
+/// # let server = Server::new("https://rpc.server", Options::default())?;
+/// let mut batch = server.batch();
+/// let latest = batch.get_latest_ledger();
+/// let tx1 = batch.get_transaction("8a3f...");
+/// let tx2 = batch.get_transaction("1c5e...");
+///
+/// let mut results = batch.send().await?;
+/// let latest = results.take(latest)?;
+/// let tx1 = results.take(tx1)?;
+/// let tx2 = results.take(tx2)?;
+/// # Ok(()) }
+/// ```
+///
+/// [JSON-RPC batch]: https://www.jsonrpc.org/specification#batch
+pub struct Batch<'a> {
+    client: &'a dyn RpcTransport,
+    calls: Vec<Result<(String, Value), Error>>,
+}
+
+impl<'a> Batch<'a> {
+    pub(crate) fn new(client: &'a dyn RpcTransport) -> Self {
+        Batch {
+            client,
+            calls: Vec::new(),
+        }
+    }
+
+    fn push<T>(&mut self, method: &str, params: Result<Value, Error>) -> BatchCall<T> {
+        self.calls.push(params.map(|p| (method.to_string(), p)));
+        BatchCall {
+            index: self.calls.len() - 1,
+            _response: PhantomData,
+        }
+    }
+
+    /// Number of queued calls
+    pub fn len(&self) -> usize {
+        self.calls.len()
+    }
+
+    /// True if no call has been queued
+    pub fn is_empty(&self) -> bool {
+        self.calls.is_empty()
+    }
+
+    /// Queue a call to [Server::get_events](crate::Server::get_events)
+    ///
+    /// The filters are never fanned out, filters over the RPC limits fail with
+    /// [Error::EventFilterLimit] even if [Options::fan_out_events](crate::Options::fan_out_events)
+    /// is set.
+    pub fn get_events(
+        &mut self,
+        ledger: Pagination,
+        filters: Vec<EventFilter>,
+        limit: impl Into<Option<u32>>,
+    ) -> BatchCall<GetEventsResponse> {
+        let params =
+            validate_event_filters(&filters).map(|_| events_params(ledger, filters, limit.into()));
+        self.push("getEvents", params)
+    }
+
+    /// Queue a call to [Server::get_fee_stats](crate::Server::get_fee_stats)
+    pub fn get_fee_stats(&mut self) -> BatchCall<GetFeeStatsResponse> {
+        self.push("getFeeStats", Ok(Value::Null))
+    }
+
+    /// Queue a call to [Server::get_health](crate::Server::get_health)
+    pub fn get_health(&mut self) -> BatchCall<GetHealthResponse> {
+        self.push("getHealth", Ok(Value::Null))
+    }
+
+    /// Queue a call to [Server::get_latest_ledger](crate::Server::get_latest_ledger)
+    pub fn get_latest_ledger(&mut self) -> BatchCall<GetLatestLedgerResponse> {
+        self.push("getLatestLedger", Ok(Value::Null))
+    }
+
+    /// Queue a call to [Server::get_ledger_entries](crate::Server::get_ledger_entries)
+    pub fn get_ledger_entries(
+        &mut self,
+        keys: Vec<LedgerKey>,
+    ) -> BatchCall<GetLedgerEntriesResponse> {
+        let params = ledger_entries_params(keys);
+        self.push("getLedgerEntries", params)
+    }
+
+    /// Queue a call to [Server::get_ledgers](crate::Server::get_ledgers)
+    pub fn get_ledgers(
+        &mut self,
+        ledger: Pagination,
+        limit: impl Into<Option<u32>>,
+    ) -> BatchCall<GetLedgersResponse> {
+        let params = paginated_params(ledger, limit.into());
+        self.push("getLedgers", Ok(params))
+    }
+
+    /// Queue a call to [Server::get_network](crate::Server::get_network)
+    pub fn get_network(&mut self) -> BatchCall<GetNetworkResponse> {
+        self.push("getNetwork", Ok(Value::Null))
+    }
+
+    /// Queue a call to [Server::get_transaction](crate::Server::get_transaction)
+    pub fn get_transaction(&mut self, hash: &str) -> BatchCall<GetTransactionResponse> {
+        self.push("getTransaction", Ok(json!({ "hash": hash })))
+    }
+
+    /// Queue a call to [Server::get_transactions](crate::Server::get_transactions)
+    pub fn get_transactions(
+        &mut self,
+        ledger: Pagination,
+        limit: impl Into<Option<u32>>,
+    ) -> BatchCall<GetTransactionsResponse> {
+        let params = paginated_params(ledger, limit.into());
+        self.push("getTransactions", Ok(params))
+    }
+
+    /// Queue a call to [Server::get_version_info](crate::Server::get_version_info)
+    pub fn get_version_info(&mut self) -> BatchCall<GetVersionInfoResponse> {
+        self.push("getVersionInfo", Ok(Value::Null))
+    }
+
+    /// Queue a call to [Server::send_transaction](crate::Server::send_transaction)
+    pub fn send_transaction(
+        &mut self,
+        transaction: &Transaction,
+    ) -> BatchCall<SendTransactionResponse> {
+        let params = send_transaction_params(transaction);
+        self.push("sendTransaction", params)
+    }
+
+    /// Queue a call to [Server::simulate_transaction](crate::Server::simulate_transaction)
+    pub fn simulate_transaction(
+        &mut self,
+        transaction: &Transaction,
+        options: Option<SimulationOptions>,
+    ) -> BatchCall<SimulateTransactionResponse> {
+        let params = simulate_transaction_params(transaction, options);
+        self.push("simulateTransaction", params)
+    }
+
+    /// Send all the queued calls in a single request
+    ///
+    /// An error is returned only if the whole batch failed. Calls whose params could not be
+    /// built are not sent, their error is returned by [BatchResponse::take].
+    pub async fn send(self) -> Result<BatchResponse, Error> {
+        let mut results: Vec<Option<Result<Value, Error>>> = Vec::with_capacity(self.calls.len());
+        let mut sent = Vec::new();
+        let mut requests = Vec::new();
+        for (index, call) in self.calls.into_iter().enumerate() {
+            match call {
+                Ok(request) => {
+                    sent.push(index);
+                    requests.push(request);
+                    results.push(None);
+                }
+                Err(e) => results.push(Some(Err(e))),
+            }
+        }
+
+        if !requests.is_empty() {
+            let span = telemetry::rpc_span("batch");
+            let start = Instant::now();
+            let responses = self
+                .client
+                .call_batch(requests)
+                .instrument(span.clone())
+                .await;
+            telemetry::record(&span, "batch", start.elapsed(), &responses);
+            let responses = responses?;
+            if responses.len() != sent.len() {
+                return Err(Error::UnexpectedError {
+                    operation: "sending the batch",
+                    reason: format!(
+                        "{} responses for a batch of {} calls",
+                        responses.len(),
+                        sent.len()
+                    ),
+                });
+            }
+            for (index, response) in sent.into_iter().zip(responses) {
+                results[index] = Some(response);
+            }
+        }
+
+        Ok(BatchResponse { results })
+    }
+}
+
+/// Results of a [Batch], retrieved with the [BatchCall] handles
+#[derive(Debug)]
+pub struct BatchResponse {
+    results: Vec<Option<Result<Value, Error>>>,
+}
+
+impl BatchResponse {
+    /// Take the result of the queued `call`
+    pub fn take<T: DeserializeOwned>(&mut self, call: BatchCall<T>) -> Result<T, Error> {
+        let result = self
+            .results
+            .get_mut(call.index)
+            .and_then(Option::take)
+            .ok_or_else(|| Error::UnexpectedError {
+                operation: "taking the batch result",
+                reason: format!("no result for the batch call {}", call.index),
+            })?;
+        handle_response(result?)
+    }
+}
@@ -0,0 +1,237 @@
+use std::future::Future;
+use std::time::Duration;
+
+use stellar_baselib::account::Account;
+use stellar_baselib::transaction::Transaction;
+use stellar_baselib::xdr::{LedgerKey, ScVal};
+use tokio::runtime::{Builder, Runtime};
+
+use crate::error::Error;
+use crate::soroban_rpc::*;
+use crate::transport::RpcTransport;
+use crate::{Durability, EventFilter, Options, Pagination, SimulationOptions};
+
+/// Blocking version of [crate::Server]
+///
+/// Every method mirrors the async method of the same name and waits for its result on an
+/// internal single threaded runtime.
+///
+/// The methods panic when called from within an async runtime, use [crate::Server] there.
+///
+/// ```rust
+/// use soroban_client::*;
+/// let rpc = blocking::Server::new("https://soroban-testnet.stellar.org", Options::default());
+/// ```
+#[derive(Debug)]
+pub struct Server {
+    inner: crate::Server,
+    runtime: Runtime,
+}
+
+impl Server {
+    /// # Instantiate a new blocking [Server], see [crate::Server::new]
+    pub fn new(server_url: &str, opts: Options) -> Result<Self, Error> {
+        Self::from_async(crate::Server::new(server_url, opts)?)
+    }
+
+    /// # Instantiate a new blocking [Server] using several RPC endpoints, see [crate::Server::with_endpoints]
+    pub fn with_endpoints(server_urls: &[&str], opts: Options) -> Result<Self, Error> {
+        Self::from_async(crate::Server::with_endpoints(server_urls, opts)?)
+    }
+
+    /// # Instantiate a new blocking [Server] using a custom [RpcTransport], see [crate::Server::with_transport]
+    pub fn with_transport(
+        transport: impl RpcTransport + 'static,
+        opts: Options,
+    ) -> Result<Self, Error> {
+        Self::from_async(crate::Server::with_transport(transport, opts))
+    }
+
+    /// # Wrap an async [crate::Server]
+    pub fn from_async(inner: crate::Server) -> Result<Self, Error> {
+        let runtime = Builder::new_current_thread().enable_all().build()?;
+        Ok(Server { inner, runtime })
+    }
+
+    /// # The wrapped async [crate::Server]
+    pub fn inner(&self) -> &crate::Server {
+        &self.inner
+    }
+
+    /// # Run a future on the internal runtime
+    ///
+    /// Useful for the methods without a blocking version, like [crate::Server::batch]:
+    /// ```rust,no_run
+    /// # use soroban_client::*;
+    /// # let rpc = blocking::Server::new("https://soroban-testnet.stellar.org", Options::default()).unwrap();
+    /// let mut batch = rpc.inner().batch();
+    /// let health = batch.get_health();
+    /// let ledger = batch.get_latest_ledger();
+    /// let results = rpc.block_on(batch.send());
+    /// ```
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+
+    /// # Call to RPC method getEvents, see [crate::Server::get_events]
+    pub fn get_events(
+        &self,
+        ledger: Pagination,
+        filters: Vec<EventFilter>,
+        limit: impl Into<Option<u32>>,
+    ) -> Result<GetEventsResponse, Error> {
+        self.block_on(self.inner.get_events(ledger, filters, limit))
+    }
+
+    /// # Call to RPC method getFeeStats, see [crate::Server::get_fee_stats]
+    pub fn get_fee_stats(&self) -> Result<GetFeeStatsResponse, Error> {
+        self.block_on(self.inner.get_fee_stats())
+    }
+
+    /// # Call to RPC method getHealth, see [crate::Server::get_health]
+    pub fn get_health(&self) -> Result<GetHealthResponse, Error> {
+        self.block_on(self.inner.get_health())
+    }
+
+    /// # Call to RPC method getLatestLedger, see [crate::Server::get_latest_ledger]
+    pub fn get_latest_ledger(&self) -> Result<GetLatestLedgerResponse, Error> {
+        self.block_on(self.inner.get_latest_ledger())
+    }
+
+    /// # Call to RPC method getLedgerEntries, see [crate::Server::get_ledger_entries]
+    pub fn get_ledger_entries(
+        &self,
+        keys: Vec<LedgerKey>,
+    ) -> Result<GetLedgerEntriesResponse, Error> {
+        self.block_on(self.inner.get_ledger_entries(keys))
+    }
+
+    /// # Call to RPC method getLedgers, see [crate::Server::get_ledgers]
+    pub fn get_ledgers(
+        &self,
+        ledger: Pagination,
+        limit: impl Into<Option<u32>>,
+    ) -> Result<GetLedgersResponse, Error> {
+        self.block_on(self.inner.get_ledgers(ledger, limit))
+    }
+
+    /// # Call to RPC method getNetwork, see [crate::Server::get_network]
+    pub fn get_network(&self) -> Result<GetNetworkResponse, Error> {
+        self.block_on(self.inner.get_network())
+    }
+
+    /// # Call to RPC method getTransaction, see [crate::Server::get_transaction]
+    pub fn get_transaction(&self, hash: &str) -> Result<GetTransactionResponse, Error> {
+        self.block_on(self.inner.get_transaction(hash))
+    }
+
+    /// # Call to RPC method getTransactions, see [crate::Server::get_transactions]
+    pub fn get_transactions(
+        &self,
+        ledger: Pagination,
+        limit: impl Into<Option<u32>>,
+    ) -> Result<GetTransactionsResponse, Error> {
+        self.block_on(self.inner.get_transactions(ledger, limit))
+    }
+
+    /// # Call to RPC method getVersionInfo, see [crate::Server::get_version_info]
+    pub fn get_version_info(&self) -> Result<GetVersionInfoResponse, Error> {
+        self.block_on(self.inner.get_version_info())
+    }
+
+    /// # Call to RPC method sendTransaction, see [crate::Server::send_transaction]
+    pub fn send_transaction(
+        &self,
+        transaction: Transaction,
+    ) -> Result<SendTransactionResponse, Error> {
+        self.block_on(self.inner.send_transaction(transaction))
+    }
+
+    /// # Call to RPC method simulateTransaction, see [crate::Server::simulate_transaction]
+    pub fn simulate_transaction(
+        &self,
+        transaction: &Transaction,
+        options: Option<SimulationOptions>,
+    ) -> Result<SimulateTransactionResponse, Error> {
+        self.block_on(self.inner.simulate_transaction(transaction, options))
+    }
+
+    /// # Fetch an [Account], see [crate::Server::get_account]
+    pub fn get_account(&self, address: &str) -> Result<Account, Error> {
+        self.block_on(self.inner.get_account(address))
+    }
+
+    /// # Fetch the ledger entry of a contract data, see [crate::Server::get_contract_data]
+    pub fn get_contract_data(
+        &self,
+        contract: &str,
+        key: ScVal,
+        durability: Durability,
+    ) -> Result<LedgerEntryResult, Error> {
+        self.block_on(self.inner.get_contract_data(contract, key, durability))
+    }
+
+    /// # Simulate and assemble a transaction, see [crate::Server::prepare_transaction]
+    pub fn prepare_transaction(&self, transaction: &Transaction) -> Result<Transaction, Error> {
+        self.block_on(self.inner.prepare_transaction(transaction))
+    }
+
+    /// # Fund an account with friendbot, see [crate::Server::request_airdrop]
+    pub fn request_airdrop(&self, account_id: &str) -> Result<Account, Error> {
+        self.block_on(self.inner.request_airdrop(account_id))
+    }
+
+    /// # Wait for a transaction to become either Success or Failed, see [crate::Server::wait_transaction]
+    #[allow(clippy::result_large_err)]
+    pub fn wait_transaction(
+        &self,
+        hash: &str,
+        max_wait: Duration,
+    ) -> Result<GetTransactionResponse, (Error, Option<GetTransactionResponse>)> {
+        self.block_on(self.inner.wait_transaction(hash, max_wait))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use serde_json::json;
+
+    use super::*;
+    use crate::transport::StubRpc;
+
+    #[test]
+    fn blocking_calls() {
+        let fake = Arc::new(StubRpc::new().with(
+            "getTransaction",
+            json!({
+                "status": "NOT_FOUND",
+                "latestLedger": StubRpc::LATEST_LEDGER,
+                "latestLedgerCloseTime": "1700000000",
+                "oldestLedger": 2500000,
+                "oldestLedgerCloseTime": "1690000000"
+            }),
+        ));
+        let s = Server::with_transport(fake.clone(), Options::default()).unwrap();
+
+        let result = s.get_latest_ledger().expect("Should not fail");
+        assert_eq!(result.sequence, StubRpc::LATEST_LEDGER);
+
+        let result = s.get_transaction("abcd").expect("Should not fail");
+        assert_eq!(result.status, TransactionStatus::NotFound);
+        assert_eq!(
+            fake.calls.lock().unwrap()[1],
+            ("getTransaction".to_string(), json!({"hash": "abcd"}))
+        );
+
+        let result = s.get_health();
+        assert!(matches!(result, Err(Error::RPCError { code: -32601, .. })));
+
+        let result = s.wait_transaction("abcd", Duration::ZERO);
+        assert!(matches!(
+            result,
+            Err((Error::WaitTransactionTimeout { .. }, None))
+        ));
+    }
+}
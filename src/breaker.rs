@@ -0,0 +1,76 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::error::Error;
+use crate::server::CircuitBreaker;
+use crate::time::Instant;
+
+/// State of a [CircuitBreaker] for one endpoint
+#[derive(Debug)]
+pub struct Breaker {
+    policy: CircuitBreaker,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum State {
+    Closed { failures: u32 },
+    Open { until: Instant },
+    // A probe was sent, it is given `reset_timeout` to complete
+    HalfOpen { since: Instant },
+}
+
+impl Breaker {
+    pub fn new(policy: &CircuitBreaker) -> Self {
+        Breaker {
+            policy: policy.clone(),
+            state: Mutex::new(State::Closed { failures: 0 }),
+        }
+    }
+
+    /// Check if a request can be sent, `Ok(true)` if the endpoint must be probed first
+    pub fn acquire(&self) -> Result<bool, Error> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        match *state {
+            State::Closed { .. } => Ok(false),
+            State::Open { until } if now < until => Err(Error::CircuitOpen {
+                retry_after: until - now,
+            }),
+            State::HalfOpen { since } if now < since + self.policy.reset_timeout => {
+                Err(Error::CircuitOpen {
+                    retry_after: since + self.policy.reset_timeout - now,
+                })
+            }
+            State::Open { .. } | State::HalfOpen { .. } => {
+                *state = State::HalfOpen { since: now };
+                Ok(true)
+            }
+        }
+    }
+
+    /// Record the outcome of a request or a probe
+    pub fn record(&self, success: bool) {
+        let mut state = self.state.lock().unwrap();
+        *state = match *state {
+            _ if success => State::Closed { failures: 0 },
+            State::Closed { failures } if failures + 1 < self.policy.failure_threshold => {
+                State::Closed {
+                    failures: failures + 1,
+                }
+            }
+            State::Open { until } => State::Open { until },
+            State::Closed { .. } | State::HalfOpen { .. } => {
+                tracing::warn!("Circuit breaker opened");
+                State::Open {
+                    until: Instant::now() + self.policy.reset_timeout,
+                }
+            }
+        };
+    }
+
+    /// Delay before the next probe, after a failed one
+    pub fn reset_timeout(&self) -> Duration {
+        self.policy.reset_timeout
+    }
+}
@@ -0,0 +1,349 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::error::Error;
+use crate::transport::{async_trait, RpcTransport};
+
+/// RPC call and its response, as stored in a [Cassette]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Interaction {
+    /// JSON-RPC method
+    pub method: String,
+    /// Params of the call
+    pub params: Value,
+    /// Result of the call, if it succeeded
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    /// Error returned by the RPC, if the call failed
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RecordedError>,
+}
+
+/// JSON-RPC error stored in a [Cassette], see [Error::RPCError]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RecordedError {
+    /// The error code returned from the RPC
+    pub code: i32,
+    /// The error message returned from the RPC
+    pub message: String,
+    /// The additional data returned from the RPC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+/// Recorded RPC traffic, stored as a JSON file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Cassette {
+    /// Calls in the order they were made
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    /// Read the cassette stored at `path`
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let text = fs::read_to_string(&path)?;
+        serde_json::from_str(&text).map_err(|source| Error::JsonError {
+            operation: "loading the cassette",
+            input: path.as_ref().display().to_string(),
+            source,
+            request_id: None,
+        })
+    }
+
+    /// Write the cassette at `path`
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let text = serde_json::to_string_pretty(self).map_err(|source| Error::JsonError {
+            operation: "saving the cassette",
+            input: path.as_ref().display().to_string(),
+            source,
+            request_id: None,
+        })?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+}
+
+/// [RpcTransport] recording the calls made through another transport into a [Cassette]
+///
+/// Call [Recorder::save] to write the cassette file once the calls are made. As a fallback,
+/// interactions not saved yet are written when the recorder is dropped, with blocking I/O, and a
+/// failure is only logged. The results of the calls are never affected. Only the responses of
+/// the RPC are recorded, network failures are returned without being recorded.
+///
+/// # Example
+/// ```rust,no_run
+/// # use std::sync::Arc;
+/// # use soroban_client::*;
+/// # use soroban_client::error::Error;
+/// use soroban_client::cassette::Recorder;
+/// use soroban_client::transport::JsonRpc;
+///
+/// # async fn record() -> Result<(), Error> {
+/// let options = Options::default();
+/// let rpc = JsonRpc::with_options("https://soroban-testnet.stellar.org", &options)?;
+/// let recorder = Arc::new(Recorder::new(rpc, "tests/cassettes/health.json"));
+/// let server = Server::with_transport(recorder.clone(), options);
+/// server.get_health().await?;
+/// recorder.save()?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug)]
+pub struct Recorder<T> {
+    inner: T,
+    path: PathBuf,
+    cassette: Mutex<Cassette>,
+    // Number of interactions written to the file
+    saved: AtomicUsize,
+}
+
+impl<T: RpcTransport> Recorder<T> {
+    /// Record the calls made through `inner` in a new cassette at `path`
+    pub fn new(inner: T, path: impl Into<PathBuf>) -> Self {
+        Recorder {
+            inner,
+            path: path.into(),
+            cassette: Mutex::new(Cassette::default()),
+            saved: AtomicUsize::new(0),
+        }
+    }
+
+    fn record(&self, method: &str, params: Value, result: &Result<Value, Error>) {
+        let (result, error) = match result {
+            Ok(value) => (Some(value.clone()), None),
+            Err(Error::RPCError {
+                code,
+                message,
+                data,
+                ..
+            }) => (
+                None,
+                Some(RecordedError {
+                    code: *code,
+                    message: message.clone(),
+                    data: data.clone(),
+                }),
+            ),
+            Err(_) => return,
+        };
+        self.cassette
+            .lock()
+            .unwrap()
+            .interactions
+            .push(Interaction {
+                method: method.to_string(),
+                params,
+                result,
+                error,
+            });
+    }
+}
+
+impl<T> Recorder<T> {
+    /// Interactions recorded so far
+    pub fn cassette(&self) -> Cassette {
+        self.cassette.lock().unwrap().clone()
+    }
+
+    /// Write the interactions recorded so far to the cassette file
+    pub fn save(&self) -> Result<(), Error> {
+        let cassette = self.cassette();
+        cassette.save(&self.path)?;
+        self.saved
+            .store(cassette.interactions.len(), Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+impl<T> Drop for Recorder<T> {
+    fn drop(&mut self) {
+        // An unused recorder does not replace an existing cassette
+        let recorded = self.cassette.lock().unwrap().interactions.len();
+        if recorded == self.saved.load(Ordering::Relaxed) {
+            return;
+        }
+        if let Err(e) = self.save() {
+            tracing::warn!(path = %self.path.display(), error = %e, "cassette not saved");
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T: RpcTransport> RpcTransport for Recorder<T> {
+    async fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+        let result = self.inner.call(method, params.clone()).await;
+        self.record(method, params, &result);
+        result
+    }
+
+    async fn call_batch(
+        &self,
+        calls: Vec<(String, Value)>,
+    ) -> Result<Vec<Result<Value, Error>>, Error> {
+        let results = self.inner.call_batch(calls.clone()).await?;
+        for ((method, params), result) in calls.into_iter().zip(&results) {
+            self.record(&method, params, result);
+        }
+        Ok(results)
+    }
+}
+
+/// [RpcTransport] serving the calls recorded in a [Cassette], without network
+///
+/// A call is matched with the first unused interaction having the same method and params, so
+/// identical calls are replayed in the order they were recorded. A call without a matching
+/// interaction returns [Error::ReplayMismatch].
+///
+/// # Example
+/// ```rust,no_run
+/// # use soroban_client::*;
+/// # use soroban_client::error::Error;
+/// use soroban_client::cassette::Replayer;
+///
+/// # async fn replay() -> Result<(), Error> {
+/// let replayer = Replayer::from_file("tests/cassettes/health.json")?;
+/// let server = Server::with_transport(replayer, Options::default());
+/// server.get_health().await?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug)]
+pub struct Replayer {
+    interactions: Vec<Interaction>,
+    used: Mutex<Vec<bool>>,
+}
+
+impl Replayer {
+    /// Replay the interactions of `cassette`
+    pub fn new(cassette: Cassette) -> Self {
+        Replayer {
+            used: Mutex::new(vec![false; cassette.interactions.len()]),
+            interactions: cassette.interactions,
+        }
+    }
+
+    /// Replay the cassette stored at `path`
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        Ok(Replayer::new(Cassette::load(path)?))
+    }
+
+    /// Number of recorded interactions not replayed yet
+    pub fn remaining(&self) -> usize {
+        self.used.lock().unwrap().iter().filter(|u| !**u).count()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl RpcTransport for Replayer {
+    async fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+        let mut used = self.used.lock().unwrap();
+        let index = self
+            .interactions
+            .iter()
+            .zip(used.iter())
+            .position(|(i, used)| !used && i.method == method && i.params == params)
+            .ok_or_else(|| Error::ReplayMismatch {
+                method: method.to_string(),
+                params: params.to_string(),
+            })?;
+        used[index] = true;
+
+        let interaction = &self.interactions[index];
+        match (&interaction.result, &interaction.error) {
+            (_, Some(e)) => Err(Error::RPCError {
+                code: e.code,
+                message: e.message.clone(),
+                data: e.data.clone(),
+                request_id: None,
+            }),
+            (Some(result), None) => Ok(result.clone()),
+            (None, None) => Ok(Value::Null),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::*;
+
+    struct Echo;
+
+    #[async_trait]
+    impl RpcTransport for Echo {
+        async fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+            match method {
+                "fail" => Err(Error::RPCError {
+                    code: -32602,
+                    message: "invalid params".into(),
+                    data: None,
+                    request_id: None,
+                }),
+                _ => Ok(json!({ "method": method, "params": params })),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn record_and_replay() {
+        let path = std::env::temp_dir().join(format!("cassette-{}.json", std::process::id()));
+        let recorder = Recorder::new(Echo, &path);
+        recorder.call("getHealth", Value::Null).await.unwrap();
+        recorder
+            .call("getLedgers", json!({"limit": 1}))
+            .await
+            .unwrap();
+        recorder
+            .call("getLedgers", json!({"limit": 2}))
+            .await
+            .unwrap();
+        recorder.call("fail", Value::Null).await.unwrap_err();
+        recorder.save().unwrap();
+
+        let cassette = Cassette::load(&path).unwrap();
+        assert_eq!(cassette, recorder.cassette());
+        drop(recorder);
+        // An unused recorder leaves the cassette as it is
+        drop(Recorder::new(Echo, &path));
+        assert_eq!(Cassette::load(&path).unwrap(), cassette);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(cassette.interactions.len(), 4);
+
+        let replayer = Replayer::new(cassette);
+        // Matched by params, not by order
+        let ledgers = replayer
+            .call("getLedgers", json!({"limit": 2}))
+            .await
+            .unwrap();
+        assert_eq!(ledgers["params"]["limit"], 2);
+        assert!(matches!(
+            replayer.call("fail", Value::Null).await,
+            Err(Error::RPCError { code: -32602, .. })
+        ));
+        assert_eq!(replayer.remaining(), 2);
+
+        // Each interaction is replayed once
+        assert!(matches!(
+            replayer.call("getLedgers", json!({"limit": 2})).await,
+            Err(Error::ReplayMismatch { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn save_on_drop() {
+        let path = std::env::temp_dir().join(format!("cassette-drop-{}.json", std::process::id()));
+        let recorder = Recorder::new(Echo, &path);
+        recorder.call("getHealth", Value::Null).await.unwrap();
+        drop(recorder);
+
+        let cassette = Cassette::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(cassette.interactions.len(), 1);
+    }
+}
@@ -0,0 +1,271 @@
+use std::time::Duration;
+
+use stellar_baselib::account::Account;
+use stellar_baselib::transaction::Transaction;
+use stellar_baselib::xdr::{LedgerKey, ScVal};
+
+use crate::error::Error;
+use crate::soroban_rpc::*;
+use crate::transport::async_trait;
+use crate::{Durability, EventFilter, Pagination, Server, SimulationOptions};
+
+/// RPC methods and helpers of [Server], to write code generic over the client
+///
+/// Downstream code can take an `impl RpcClient` or a `&dyn RpcClient` instead of a [Server], and
+/// its tests can use an in-memory implementation. Every method is required. For a partial fake,
+/// build a [Server] with [Server::with_transport] over an
+/// [RpcTransport](crate::transport::RpcTransport) answering only the methods needed, the others
+/// fail with [Error::RPCError]. The `testing` feature provides such a transport.
+///
+/// The `limit` parameters are `Option<u32>` to keep the trait object safe.
+///
+/// # Example
+/// ```rust
+/// # use soroban_client::*;
+/// # use soroban_client::client::RpcClient;
+/// # use soroban_client::error::Error;
+/// # use soroban_client::transport::RpcTransport;
+/// use serde_json::{json, Value};
+///
+/// async fn next_ledger(rpc: &impl RpcClient) -> Result<u32, Error> {
+///     Ok(rpc.get_latest_ledger().await?.sequence + 1)
+/// }
+///
+/// // Answers only getLatestLedger
+/// struct Fake;
+///
+/// #[soroban_client::transport::async_trait]
+/// impl RpcTransport for Fake {
+///     async fn call(&self, method: &str, _params: Value) -> Result<Value, Error> {
+///         match method {
+///             "getLatestLedger" => Ok(json!({
+///                 "id": "c73c5eac58a441d4eb733c35253ae85f783e018f7be5ef974258fed067aabb36",
+///                 "protocolVersion": 22,
+///                 "sequence": 100
+///             })),
+///             _ => Err(Error::RPCError {
+///                 code: -32601,
+///                 message: "method not found".into(),
+///                 data: None,
+///                 request_id: None,
+///             }),
+///         }
+///     }
+/// }
+///
+/// # async fn run() -> Result<(), Error> {
+/// let server = Server::with_transport(Fake, Options::default());
+/// assert_eq!(next_ledger(&server).await?, 101);
+/// # Ok(()) }
+/// ```
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait RpcClient: Send + Sync {
+    /// See [Server::get_events]
+    async fn get_events(
+        &self,
+        ledger: Pagination,
+        filters: Vec<EventFilter>,
+        limit: Option<u32>,
+    ) -> Result<GetEventsResponse, Error>;
+
+    /// See [Server::get_fee_stats]
+    async fn get_fee_stats(&self) -> Result<GetFeeStatsResponse, Error>;
+
+    /// See [Server::get_health]
+    async fn get_health(&self) -> Result<GetHealthResponse, Error>;
+
+    /// See [Server::get_latest_ledger]
+    async fn get_latest_ledger(&self) -> Result<GetLatestLedgerResponse, Error>;
+
+    /// See [Server::get_ledger_entries]
+    async fn get_ledger_entries(
+        &self,
+        keys: Vec<LedgerKey>,
+    ) -> Result<GetLedgerEntriesResponse, Error>;
+
+    /// See [Server::get_ledgers]
+    async fn get_ledgers(
+        &self,
+        ledger: Pagination,
+        limit: Option<u32>,
+    ) -> Result<GetLedgersResponse, Error>;
+
+    /// See [Server::get_network]
+    async fn get_network(&self) -> Result<GetNetworkResponse, Error>;
+
+    /// See [Server::get_transaction]
+    async fn get_transaction(&self, hash: &str) -> Result<GetTransactionResponse, Error>;
+
+    /// See [Server::get_transactions]
+    async fn get_transactions(
+        &self,
+        ledger: Pagination,
+        limit: Option<u32>,
+    ) -> Result<GetTransactionsResponse, Error>;
+
+    /// See [Server::get_version_info]
+    async fn get_version_info(&self) -> Result<GetVersionInfoResponse, Error>;
+
+    /// See [Server::send_transaction]
+    async fn send_transaction(
+        &self,
+        transaction: Transaction,
+    ) -> Result<SendTransactionResponse, Error>;
+
+    /// See [Server::simulate_transaction]
+    async fn simulate_transaction(
+        &self,
+        transaction: &Transaction,
+        options: Option<SimulationOptions>,
+    ) -> Result<SimulateTransactionResponse, Error>;
+
+    /// See [Server::get_account]
+    async fn get_account(&self, address: &str) -> Result<Account, Error>;
+
+    /// See [Server::get_contract_data]
+    async fn get_contract_data(
+        &self,
+        contract: &str,
+        key: ScVal,
+        durability: Durability,
+    ) -> Result<LedgerEntryResult, Error>;
+
+    /// See [Server::prepare_transaction]
+    async fn prepare_transaction(&self, transaction: &Transaction) -> Result<Transaction, Error>;
+
+    /// See [Server::request_airdrop]
+    async fn request_airdrop(&self, account_id: &str) -> Result<Account, Error>;
+
+    /// See [Server::wait_transaction]
+    async fn wait_transaction(
+        &self,
+        hash: &str,
+        max_wait: Duration,
+    ) -> Result<GetTransactionResponse, (Error, Option<GetTransactionResponse>)>;
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl RpcClient for Server {
+    async fn get_events(
+        &self,
+        ledger: Pagination,
+        filters: Vec<EventFilter>,
+        limit: Option<u32>,
+    ) -> Result<GetEventsResponse, Error> {
+        Server::get_events(self, ledger, filters, limit).await
+    }
+
+    async fn get_fee_stats(&self) -> Result<GetFeeStatsResponse, Error> {
+        Server::get_fee_stats(self).await
+    }
+
+    async fn get_health(&self) -> Result<GetHealthResponse, Error> {
+        Server::get_health(self).await
+    }
+
+    async fn get_latest_ledger(&self) -> Result<GetLatestLedgerResponse, Error> {
+        Server::get_latest_ledger(self).await
+    }
+
+    async fn get_ledger_entries(
+        &self,
+        keys: Vec<LedgerKey>,
+    ) -> Result<GetLedgerEntriesResponse, Error> {
+        Server::get_ledger_entries(self, keys).await
+    }
+
+    async fn get_ledgers(
+        &self,
+        ledger: Pagination,
+        limit: Option<u32>,
+    ) -> Result<GetLedgersResponse, Error> {
+        Server::get_ledgers(self, ledger, limit).await
+    }
+
+    async fn get_network(&self) -> Result<GetNetworkResponse, Error> {
+        Server::get_network(self).await
+    }
+
+    async fn get_transaction(&self, hash: &str) -> Result<GetTransactionResponse, Error> {
+        Server::get_transaction(self, hash).await
+    }
+
+    async fn get_transactions(
+        &self,
+        ledger: Pagination,
+        limit: Option<u32>,
+    ) -> Result<GetTransactionsResponse, Error> {
+        Server::get_transactions(self, ledger, limit).await
+    }
+
+    async fn get_version_info(&self) -> Result<GetVersionInfoResponse, Error> {
+        Server::get_version_info(self).await
+    }
+
+    async fn send_transaction(
+        &self,
+        transaction: Transaction,
+    ) -> Result<SendTransactionResponse, Error> {
+        Server::send_transaction(self, transaction).await
+    }
+
+    async fn simulate_transaction(
+        &self,
+        transaction: &Transaction,
+        options: Option<SimulationOptions>,
+    ) -> Result<SimulateTransactionResponse, Error> {
+        Server::simulate_transaction(self, transaction, options).await
+    }
+
+    async fn get_account(&self, address: &str) -> Result<Account, Error> {
+        Server::get_account(self, address).await
+    }
+
+    async fn get_contract_data(
+        &self,
+        contract: &str,
+        key: ScVal,
+        durability: Durability,
+    ) -> Result<LedgerEntryResult, Error> {
+        Server::get_contract_data(self, contract, key, durability).await
+    }
+
+    async fn prepare_transaction(&self, transaction: &Transaction) -> Result<Transaction, Error> {
+        Server::prepare_transaction(self, transaction).await
+    }
+
+    async fn request_airdrop(&self, account_id: &str) -> Result<Account, Error> {
+        Server::request_airdrop(self, account_id).await
+    }
+
+    async fn wait_transaction(
+        &self,
+        hash: &str,
+        max_wait: Duration,
+    ) -> Result<GetTransactionResponse, (Error, Option<GetTransactionResponse>)> {
+        Server::wait_transaction(self, hash, max_wait).await
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::transport::StubRpc;
+    use crate::Options;
+
+    async fn latest(rpc: &dyn RpcClient) -> Result<u32, Error> {
+        Ok(rpc.get_latest_ledger().await?.sequence)
+    }
+
+    #[tokio::test]
+    async fn generic_client() {
+        let server = Server::with_transport(StubRpc::new(), Options::default());
+        assert_eq!(latest(&server).await.unwrap(), StubRpc::LATEST_LEDGER);
+        assert!(matches!(
+            RpcClient::get_health(&server).await,
+            Err(Error::RPCError { code: -32601, .. })
+        ));
+    }
+}
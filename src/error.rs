@@ -1,8 +1,18 @@
 /// Error Handling in `soroban_client` crate
 /// This module defines all possible error types used in the `soroban_client` crate.
-use stellar_baselib::xdr::SorobanTransactionData;
+use std::time::Duration;
+use stellar_baselib::xdr::{ScVal, SorobanTransactionData};
 use thiserror::Error;
 
+/// Message of an underlying error that cannot be kept as a source, because it is not
+/// `Send + Sync` or does not implement [std::error::Error], as the errors of `stellar_baselib`
+#[derive(Error, Debug, Clone, PartialEq, Eq)]
+#[error("{0}")]
+pub struct Cause(pub String);
+
+/// Source of an [Error], the typed error when it is `Send + Sync`, or a [Cause] otherwise
+pub type BoxedSource = Box<dyn std::error::Error + Send + Sync>;
+
 /// Possible error for AuthMode in simulation request
 #[derive(Error, Debug)]
 pub enum AuthModeError {
@@ -18,52 +28,342 @@ pub enum Error {
     #[error(transparent)]
     InvalidRpc(#[from] InvalidRpcUrl),
     /// Error when XDR processing fails
-    #[error("XdrError")]
-    XdrError,
-    /// Error when JSON parsing fails, with a descriptive message
-    #[error("JsonError: could not parse {0}")]
-    JsonError(String),
+    #[error("XdrError while {operation}: {source}")]
+    XdrError {
+        /// What was being encoded or decoded
+        operation: &'static str,
+        /// The XDR error
+        #[source]
+        source: stellar_baselib::xdr::Error,
+    },
+    /// Error when JSON parsing or serialization fails
+    #[error("JsonError while {operation}: {source}, in {input}{}", for_request(.request_id))]
+    JsonError {
+        /// What was being parsed or serialized
+        operation: &'static str,
+        /// The JSON text that could not be parsed, or what could not be serialized
+        input: String,
+        /// The JSON error
+        #[source]
+        source: serde_json::Error,
+        /// Id of the JSON-RPC request, if the error happened while sending it
+        request_id: Option<u64>,
+    },
     /// Error for network-related failures
-    #[error("NetworkError")]
-    NetworkError(#[from] reqwest::Error),
+    #[error("NetworkError: {source}{}", for_request(.request_id))]
+    NetworkError {
+        /// The HTTP client error
+        #[source]
+        source: reqwest::Error,
+        /// Id of the JSON-RPC request, if the error happened while sending it
+        request_id: Option<u64>,
+    },
+    /// Error when an address or a public key cannot be parsed
+    #[error("InvalidAddress `{address}`: {source}")]
+    InvalidAddress {
+        /// The address
+        address: String,
+        /// Why it is invalid
+        #[source]
+        source: Cause,
+    },
     /// Error when an account is not found
-    #[error("AccountError")]
-    AccountNotFound,
+    #[error("AccountNotFound: {address}")]
+    AccountNotFound {
+        /// Address of the account
+        address: String,
+    },
     /// Error when contract data is missing
-    #[error("ContractError")]
-    ContractDataNotFound,
+    #[error("ContractDataNotFound: no entry for key {key:?} of contract {contract}")]
+    ContractDataNotFound {
+        /// Address of the contract
+        contract: String,
+        /// Key of the contract data
+        key: ScVal,
+    },
     /// Error for general transaction failures
-    #[error("TransactionError")]
-    TransactionError,
+    #[error("TransactionError while {operation} for transaction {hash}: {source}")]
+    TransactionError {
+        /// What was being done with the transaction
+        operation: &'static str,
+        /// Hash of the transaction, hex encoded
+        hash: String,
+        /// The underlying error
+        #[source]
+        source: Cause,
+    },
     /// Error for invalid Soroban transactions
     #[error("InvalidSorobanTransaction")]
     InvalidSorobanTransaction,
     /// Error when a simulation fails
-    #[error("SimulationFailed: `{0}`")]
-    SimulationFailed(String),
+    #[error("SimulationFailed for transaction {hash}: `{error}`")]
+    SimulationFailed {
+        /// Hash of the simulated transaction, hex encoded
+        hash: String,
+        /// The error returned by the simulation
+        error: String,
+    },
     /// Error when restoration is required with additional data
-    #[error("RestorationRequired")]
-    RestorationRequired(i64, SorobanTransactionData),
+    #[error("RestorationRequired for transaction {hash}, minimum resource fee {min_resource_fee}")]
+    RestorationRequired {
+        /// Hash of the simulated transaction, hex encoded
+        hash: String,
+        /// Minimum resource fee of the restore transaction
+        min_resource_fee: i64,
+        /// Data of the restore transaction
+        transaction_data: Box<SorobanTransactionData>,
+    },
+    /// Error when a field of an RPC response is missing or invalid
+    #[error("InvalidResponse: `{field}` {source}")]
+    InvalidResponse {
+        /// Name of the field in the response
+        field: &'static str,
+        /// Why it is invalid, the parsing error or a [Cause]
+        #[source]
+        source: BoxedSource,
+    },
     /// Error for RPC failures, includes code and message
-    #[error("RPCError {code}: {message}")]
+    ///
+    /// Use [Error::rpc_code] to classify the `code`.
+    #[error("RPCError {code}: {message}{}", for_request(.request_id))]
     RPCError {
         /// The error code returned from the RPC
         code: i32,
         /// The error message returned from the RPC
         message: String,
+        /// The additional `data` returned from the RPC, if any
+        data: Option<serde_json::Value>,
+        /// Id of the JSON-RPC request, `None` for a whole batch or a fake RPC
+        request_id: Option<u64>,
     },
     /// Unexpected error, should be reported
-    #[error("UnexpectedError")]
-    UnexpectedError,
+    #[error("UnexpectedError while {operation}: {reason}")]
+    UnexpectedError {
+        /// What was being done
+        operation: &'static str,
+        /// What happened
+        reason: String,
+    },
     /// Error when Friendbot is not available on the current network
     #[error("NoFriendbot")]
     NoFriendbot,
+    /// Error when the RPC did not respond within [Options::timeout](crate::Options::timeout)
+    ///
+    /// Only on wasm32, native timeouts are reported as a [Error::NetworkError].
+    #[error("RequestTimeout: no response after {0:?}")]
+    RequestTimeout(Duration),
     /// Error when a timeout occurs
-    #[error("Timeout of {0}s reached after {1}s while waiting for a transaction to complete")]
-    WaitTransactionTimeout(u64, u64),
+    #[error(
+        "Timeout of {max_wait}s reached after {waited}s while waiting for transaction {hash} to complete"
+    )]
+    WaitTransactionTimeout {
+        /// Hash of the transaction
+        hash: String,
+        /// Maximum duration to wait, in seconds
+        max_wait: u64,
+        /// Time waited, in seconds
+        waited: u64,
+    },
     /// Error when a function is not implemented, you probably need to enable a feature
     #[error("NotImplemented: `{0}`")]
     NotImplemented(String),
+    /// Error when an RPC endpoint does not serve the same network as the others
+    #[error("NetworkMismatch: {endpoint} serves `{found}` instead of `{expected}`")]
+    NetworkMismatch {
+        /// URL of the endpoint
+        endpoint: String,
+        /// Network passphrase of the other endpoints
+        expected: String,
+        /// Network passphrase of this endpoint
+        found: String,
+    },
+    /// Error when the RPC rejected the request with HTTP status 429 Too Many Requests
+    #[error("RateLimited, retry after {retry_after:?}")]
+    RateLimited {
+        /// Delay requested by the RPC with the `Retry-After` header, in seconds or as an HTTP-date
+        retry_after: Option<Duration>,
+    },
+    /// Error when the circuit breaker of the endpoint is open, see
+    /// [CircuitBreaker](crate::CircuitBreaker)
+    #[error("CircuitOpen, retry after {retry_after:?}")]
+    CircuitOpen {
+        /// Delay before the endpoint is probed again
+        retry_after: Duration,
+    },
+    /// Error when none of the RPC endpoints can be used
+    #[error("NoHealthyEndpoint")]
+    NoHealthyEndpoint,
+    /// Error when the id of a JSON-RPC response is not the id of the request
+    #[error("ResponseIdMismatch: expected id {expected}, found {found:?}")]
+    ResponseIdMismatch {
+        /// Id of the request
+        expected: u64,
+        /// Id of the response
+        found: Option<u64>,
+    },
+    /// Error when the event filters exceed a limit of the RPC, see
+    /// [Options::fan_out_events](crate::Options::fan_out_events)
+    #[error("EventFilterLimit: {count} {limit}, the maximum is {max}")]
+    EventFilterLimit {
+        /// The limit exceeded
+        limit: &'static str,
+        /// Number of filters, contract IDs or topics
+        count: usize,
+        /// Maximum allowed by the RPC
+        max: usize,
+    },
+    /// Error when a contract event does not match the type it is decoded into, see
+    /// [FromContractEvent](crate::events::FromContractEvent)
+    #[error("InvalidEvent: cannot decode {target}, {reason}")]
+    InvalidEvent {
+        /// Name of the type
+        target: &'static str,
+        /// Why the event does not match
+        reason: String,
+    },
+    /// Error returned by the [EventSink](crate::ingest::EventSink) of an ingestion
+    #[error("EventSink: {0}")]
+    EventSink(#[source] Box<dyn std::error::Error + Send + Sync>),
+    /// Error when reading or writing a file
+    #[error("IoError: {0}")]
+    IoError(#[from] std::io::Error),
+    /// Error when a replayed call was not recorded in the cassette
+    #[error("ReplayMismatch: no recorded `{method}` call with params {params}")]
+    ReplayMismatch {
+        /// JSON-RPC method of the call
+        method: String,
+        /// Params of the call, as JSON
+        params: String,
+    },
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(source: reqwest::Error) -> Self {
+        Error::NetworkError {
+            source,
+            request_id: None,
+        }
+    }
+}
+
+impl Error {
+    /// Id of the JSON-RPC request that failed, to find it in the logs of the RPC provider
+    pub fn request_id(&self) -> Option<u64> {
+        match self {
+            Error::JsonError { request_id, .. }
+            | Error::NetworkError { request_id, .. }
+            | Error::RPCError { request_id, .. } => *request_id,
+            Error::ResponseIdMismatch { expected, .. } => Some(*expected),
+            _ => None,
+        }
+    }
+
+    // Set the id of the request that failed, if the error can carry it
+    pub(crate) fn with_request_id(mut self, id: u64) -> Self {
+        if let Error::JsonError { request_id, .. }
+        | Error::NetworkError { request_id, .. }
+        | Error::RPCError { request_id, .. } = &mut self
+        {
+            request_id.get_or_insert(id);
+        }
+        self
+    }
+
+    /// Classification of the code of an [Error::RPCError]
+    pub fn rpc_code(&self) -> Option<RpcErrorCode> {
+        match self {
+            Error::RPCError { code, message, .. } => Some(RpcErrorCode::new(*code, message)),
+            _ => None,
+        }
+    }
+
+    /// Whether the same request can succeed if sent again later
+    ///
+    /// This is the case for network failures, rate limiting, unavailable endpoints and internal
+    /// RPC errors. Invalid requests, missing data and mismatched response ids, which mean a
+    /// broken proxy or provider, are not retryable.
+    pub fn is_retryable(&self) -> bool {
+        self.is_unavailable()
+            || matches!(self, Error::NoHealthyEndpoint)
+            || matches!(
+                self.rpc_code(),
+                Some(RpcErrorCode::Internal | RpcErrorCode::Server(_))
+            )
+    }
+
+    // Errors meaning the endpoint is unavailable, not that the request is invalid
+    pub(crate) fn is_unavailable(&self) -> bool {
+        match self {
+            // Only a malformed response of the RPC, decoding a valid result cannot succeed later
+            Error::JsonError { operation, .. } => {
+                [PARSING_RESPONSE, PARSING_BATCH_RESPONSE].contains(operation)
+            }
+            _ => matches!(
+                self,
+                Error::NetworkError { .. }
+                    | Error::RequestTimeout(_)
+                    | Error::RateLimited { .. }
+                    | Error::CircuitOpen { .. }
+            ),
+        }
+    }
+}
+
+/// Classification of the JSON-RPC error codes returned by the RPC, see [Error::rpc_code]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpcErrorCode {
+    /// Invalid JSON received by the RPC (-32700)
+    ParseError,
+    /// The request is not a valid JSON-RPC request (-32600)
+    InvalidRequest,
+    /// The method does not exist (-32601)
+    MethodNotFound,
+    /// Invalid method parameters (-32602)
+    InvalidParams,
+    /// Internal error of the RPC (-32603)
+    Internal,
+    /// The start ledger is outside of the ledgers retained by the RPC, see
+    /// [GetHealthResponse::oldest_ledger](crate::soroban_rpc::GetHealthResponse::oldest_ledger)
+    StartLedgerOutOfRange,
+    /// Implementation defined server error (-32000 to -32099)
+    Server(i32),
+    /// Any other code
+    Other(i32),
+}
+
+impl RpcErrorCode {
+    /// Classify the `code` of an error, the `message` is used to detect the Stellar RPC failures
+    pub fn new(code: i32, message: &str) -> Self {
+        match code {
+            -32600 | -32602 if is_start_ledger_out_of_range(message) => {
+                RpcErrorCode::StartLedgerOutOfRange
+            }
+            -32700 => RpcErrorCode::ParseError,
+            -32600 => RpcErrorCode::InvalidRequest,
+            -32601 => RpcErrorCode::MethodNotFound,
+            -32602 => RpcErrorCode::InvalidParams,
+            -32603 => RpcErrorCode::Internal,
+            -32099..=-32000 => RpcErrorCode::Server(code),
+            _ => RpcErrorCode::Other(code),
+        }
+    }
+}
+
+// Stellar RPC has no dedicated code for this failure, only the message of getEvents and
+// getLedgers: "startLedger must be between the oldest ledger: X and the latest ledger: Y"
+fn is_start_ledger_out_of_range(message: &str) -> bool {
+    message.starts_with("startLedger must be between the oldest ledger")
+}
+
+// Operations of the JsonError when the JSON-RPC response itself is malformed
+pub(crate) const PARSING_RESPONSE: &str = "parsing the response";
+pub(crate) const PARSING_BATCH_RESPONSE: &str = "parsing the batch response";
+
+fn for_request(request_id: &Option<u64>) -> String {
+    match request_id {
+        Some(id) => format!(" (request {id})"),
+        None => String::new(),
+    }
 }
 
 /// Possible  errors for invalid RPC URLs
@@ -76,6 +376,15 @@ pub enum InvalidRpcUrl {
     #[error("Http scheme requires the option allow_http: true")]
     UnsecureHttpNotAllowed,
     /// Error when the provided URL is invalid
-    #[error("InvalidUrl")]
-    InvalidUri,
+    #[error("InvalidUrl `{url}`: {source}")]
+    InvalidUri {
+        /// The URL
+        url: String,
+        /// Why it is invalid
+        #[source]
+        source: url::ParseError,
+    },
+    /// Error when no URL is provided
+    #[error("No RPC Url")]
+    NoUrl,
 }
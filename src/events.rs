@@ -0,0 +1,785 @@
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures::stream::{self, Stream, StreamExt, TryStreamExt};
+
+use stellar_baselib::xdr::{ContractEvent, ContractEventBody, ScAddress, ScVal};
+
+use crate::error::Error;
+use crate::soroban_rpc::{EventResponse, GetEventsResponse};
+use crate::time::sleep;
+use crate::{EventFilter, Pagination, Server, MAX_EVENT_FILTERS, MAX_FAN_OUT_REQUESTS};
+
+/// Derive macro of [FromContractEvent], see the trait for an example
+pub use soroban_client_derive::FromContractEvent;
+
+#[cfg(not(target_arch = "wasm32"))]
+type BoxedStream<'a, T> = stream::BoxStream<'a, T>;
+#[cfg(target_arch = "wasm32")]
+type BoxedStream<'a, T> = stream::LocalBoxStream<'a, T>;
+
+impl Server {
+    /// Stream of the events matching `filters`, fetched page by page from `start`
+    ///
+    /// The stream follows the cursor of each [GetEventsResponse](crate::soroban_rpc::GetEventsResponse)
+    /// and ends once it reaches the latest ledger, or the end ledger of
+    /// [Pagination::FromTo]. Use [EventStream::tail] to keep polling for new events instead.
+    /// Events already returned are skipped.
+    ///
+    /// The stream ends after returning an error. It can be resumed with
+    /// `Pagination::Cursor(id)`, where `id` is the [id](EventResponse::id) of the last event
+    /// received.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// # use std::time::Duration;
+    /// # use futures::StreamExt;
+    /// # use soroban_client::soroban_rpc::*;
+    /// # use soroban_client::*;
+    /// # use soroban_client::error::Error;
+    /// # async fn events() -> Result<(), Error> {
+    /// # let server = Server::new("https://rpc.server", Options::default())?;
+    /// let filter = EventFilter::new(EventType::Contract).contract("CAA...");
+    /// let mut events = server
+    ///     .events_stream(Pagination::From(67000), vec![filter])
+    ///     .tail(Duration::from_secs(5));
+    /// while let Some(event) = events.next().await {
+    ///     println!("{}", event?.id);
+    /// }
+    /// # Ok(()) }
+    /// ```
+    pub fn events_stream(&self, start: Pagination, filters: Vec<EventFilter>) -> EventStream<'_> {
+        EventStream {
+            server: self,
+            config: Some(Config {
+                start,
+                filters,
+                limit: None,
+                tail: None,
+            }),
+            inner: None,
+        }
+    }
+
+    // Send the filters over the RPC limits in several requests, see Options::fan_out_events
+    pub(crate) async fn get_events_fanned_out(
+        &self,
+        ledger: Pagination,
+        filters: Vec<EventFilter>,
+        limit: Option<u32>,
+    ) -> Result<GetEventsResponse, Error> {
+        let filters: Vec<EventFilter> = filters.iter().flat_map(EventFilter::split).collect();
+        let requests: Vec<_> = filters
+            .chunks(MAX_EVENT_FILTERS)
+            .map(|filters| self.get_events(ledger.clone(), filters.to_vec(), limit))
+            .collect();
+        // The remaining requests are not sent once one fails
+        let responses = stream::iter(requests)
+            .buffered(MAX_FAN_OUT_REQUESTS)
+            .try_collect()
+            .await?;
+        Ok(merge_events(responses, limit))
+    }
+}
+
+// Merge the responses of the same page for different filters
+//
+// Each response covers the events up to its cursor, so the merged page stops at the smallest
+// cursor. Event ids and cursors are zero padded, they are ordered as strings.
+fn merge_events(responses: Vec<GetEventsResponse>, limit: Option<u32>) -> GetEventsResponse {
+    let end = responses.iter().filter_map(|r| r.cursor.clone()).min();
+    let latest_ledger = responses.iter().map(|r| r.latest_ledger).min();
+    let oldest_ledger = responses.iter().filter_map(|r| r.oldest_ledger).max();
+
+    let mut responses = responses.into_iter();
+    let mut merged = responses.next().unwrap_or_else(|| GetEventsResponse {
+        events: Vec::new(),
+        cursor: None,
+        latest_ledger: 0,
+        oldest_ledger: None,
+        latest_ledger_close_time: None,
+        oldest_ledger_close_time: None,
+    });
+    merged.events.extend(responses.flat_map(|r| r.events));
+    if let Some(end) = &end {
+        merged.events.retain(|e| e.id <= *end);
+    }
+    merged
+        .events
+        .sort_by(|a, b| (a.ledger, &a.id).cmp(&(b.ledger, &b.id)));
+    merged.events.dedup_by(|a, b| a.id == b.id);
+
+    merged.cursor = end;
+    if let Some(limit) = limit.map(|l| l as usize) {
+        if merged.events.len() > limit {
+            merged.events.truncate(limit);
+            merged.cursor = merged.events.last().map(|e| e.id.clone());
+        }
+    }
+    merged.latest_ledger = latest_ledger.unwrap_or(merged.latest_ledger);
+    merged.oldest_ledger = oldest_ledger.or(merged.oldest_ledger);
+    merged
+}
+
+/// Stream of events returned by [Server::events_stream]
+///
+/// The stream is configured with [EventStream::limit] and [EventStream::tail] before being
+/// polled for the first time, these methods have no effect afterwards.
+pub struct EventStream<'a> {
+    server: &'a Server,
+    config: Option<Config>,
+    inner: Option<BoxedStream<'a, Result<EventResponse, Error>>>,
+}
+
+struct Config {
+    start: Pagination,
+    filters: Vec<EventFilter>,
+    limit: Option<u32>,
+    tail: Option<Duration>,
+}
+
+impl EventStream<'_> {
+    /// Maximum number of events fetched per request, the RPC default if not set
+    pub fn limit(mut self, limit: u32) -> Self {
+        if let Some(config) = &mut self.config {
+            config.limit = Some(limit);
+        }
+        self
+    }
+
+    /// Keep polling for new events once the latest ledger is reached, waiting `poll_interval`
+    /// between two requests
+    pub fn tail(mut self, poll_interval: Duration) -> Self {
+        if let Some(config) = &mut self.config {
+            config.tail = Some(poll_interval);
+        }
+        self
+    }
+}
+
+impl std::fmt::Debug for EventStream<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStream")
+            .field("started", &self.inner.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Stream for EventStream<'_> {
+    type Item = Result<EventResponse, Error>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = &mut *self;
+        let inner = match (&mut this.inner, this.config.take()) {
+            (Some(inner), _) => inner,
+            (inner, config) => inner.insert(pages(this.server, config.expect("polled once"))),
+        };
+        inner.poll_next_unpin(cx)
+    }
+}
+
+struct State<'a> {
+    server: &'a Server,
+    next: Pagination,
+    filters: Vec<EventFilter>,
+    limit: Option<u32>,
+    tail: Option<Duration>,
+    end: Option<u32>,
+    seen: Seen,
+    waiting: bool,
+}
+
+fn pages(server: &Server, config: Config) -> BoxedStream<'_, Result<EventResponse, Error>> {
+    let end = match config.start {
+        Pagination::FromTo(_, end) => Some(end),
+        _ => None,
+    };
+    let state = State {
+        server,
+        next: config.start,
+        filters: config.filters,
+        limit: config.limit,
+        tail: config.tail,
+        end,
+        seen: Seen::default(),
+        waiting: false,
+    };
+    let pages = stream::unfold(Some(state), |state| async move {
+        let mut state = state?;
+        let page = state.next_page().await;
+        let state = match &page {
+            Ok((_, done)) if !done => Some(state),
+            _ => None,
+        };
+        Some((page.map(|(events, _)| events), state))
+    })
+    .flat_map(|page| {
+        stream::iter(match page {
+            Ok(events) => events.into_iter().map(Ok).collect(),
+            Err(e) => vec![Err(e)],
+        })
+    });
+
+    #[cfg(not(target_arch = "wasm32"))]
+    return pages.boxed();
+    #[cfg(target_arch = "wasm32")]
+    return pages.boxed_local();
+}
+
+impl State<'_> {
+    // Next events, and whether the stream is complete
+    async fn next_page(&mut self) -> Result<(Vec<EventResponse>, bool), Error> {
+        loop {
+            if self.waiting {
+                sleep(self.tail.unwrap_or_default()).await;
+                self.waiting = false;
+            }
+            let response = self
+                .server
+                .get_events(self.next.clone(), self.filters.clone(), self.limit)
+                .await?;
+
+            let caught_up = response.events.is_empty();
+            if let Some(cursor) = response.cursor {
+                self.next = Pagination::Cursor(cursor);
+            } else if let Some(last) = response.events.last() {
+                self.next = Pagination::Cursor(last.id.clone());
+            }
+
+            let mut done = false;
+            let mut events = Vec::with_capacity(response.events.len());
+            for event in response.events {
+                if self.end.is_some_and(|end| event.ledger >= u64::from(end)) {
+                    done = true;
+                    break;
+                }
+                if self.seen.insert(&event) {
+                    events.push(event);
+                }
+            }
+
+            if caught_up {
+                let reached_end = self
+                    .end
+                    .is_some_and(|end| response.latest_ledger >= u64::from(end));
+                if reached_end || self.tail.is_none() {
+                    return Ok((events, true));
+                }
+                self.waiting = true;
+                continue;
+            }
+            return Ok((events, done));
+        }
+    }
+}
+
+// Ids of the events of the most recent ledger, the events being ordered by ledger
+#[derive(Default)]
+struct Seen {
+    ledger: u64,
+    ids: HashSet<String>,
+}
+
+impl Seen {
+    // Whether the event was not seen before
+    fn insert(&mut self, event: &EventResponse) -> bool {
+        if event.ledger < self.ledger {
+            return false;
+        }
+        if event.ledger > self.ledger {
+            self.ledger = event.ledger;
+            self.ids.clear();
+        }
+        self.ids.insert(event.id.clone())
+    }
+}
+
+/// Decoding of a contract event into a Rust type
+///
+/// Derive it with `#[derive(FromContractEvent)]`, mapping each field to a topic or to the
+/// value of the event. The type of each field implements [FromScVal].
+///
+/// # Example
+/// ```rust
+/// # use soroban_client::error::Error;
+/// # use soroban_client::soroban_rpc::EventResponse;
+/// use soroban_client::events::FromContractEvent;
+///
+/// #[derive(FromContractEvent)]
+/// #[event(name = "transfer")]
+/// struct TransferEvent {
+///     #[event(topic = 1)]
+///     from: String,
+///     #[event(topic = 2)]
+///     to: String,
+///     #[event(value)]
+///     amount: i128,
+/// }
+///
+/// fn transfers(events: &[EventResponse]) -> Result<Vec<TransferEvent>, Error> {
+///     events.iter().map(TransferEvent::from_event_response).collect()
+/// }
+/// ```
+pub trait FromContractEvent: Sized {
+    /// Decode the event from its `topics` and `value`, or return [Error::InvalidEvent]
+    fn from_contract_event(topics: &[ScVal], value: &ScVal) -> Result<Self, Error>;
+
+    /// Decode an event returned by [Server::get_events]
+    fn from_event_response(event: &EventResponse) -> Result<Self, Error> {
+        Self::from_contract_event(&event.try_topic()?, &event.try_value()?)
+    }
+
+    /// Decode an XDR event, such as the events of
+    /// [TransactionDetails::to_events](crate::soroban_rpc::TransactionDetails::to_events)
+    fn from_xdr_event(event: &ContractEvent) -> Result<Self, Error> {
+        let ContractEventBody::V0(body) = &event.body;
+        Self::from_contract_event(&body.topics, &body.data)
+    }
+}
+
+/// Conversion of a [ScVal] into a field of a [FromContractEvent] type
+pub trait FromScVal: Sized {
+    /// Convert `val`, or describe why it does not match
+    fn from_sc_val(val: &ScVal) -> Result<Self, String>;
+
+    /// Value of a missing map entry, an error unless the type is an [Option]
+    fn missing() -> Result<Self, String> {
+        Err("missing".to_string())
+    }
+}
+
+fn mismatch<T>(expected: &str, found: &ScVal) -> Result<T, String> {
+    Err(format!(
+        "expected {expected}, found {}",
+        found.discriminant().name()
+    ))
+}
+
+macro_rules! from_sc_val {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl FromScVal for $ty {
+                fn from_sc_val(val: &ScVal) -> Result<Self, String> {
+                    match val {
+                        ScVal::$variant(v) => Ok(*v),
+                        val => mismatch(stringify!($variant), val),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+from_sc_val!(bool => Bool, u32 => U32, i32 => I32, u64 => U64, i64 => I64);
+
+impl FromScVal for u128 {
+    fn from_sc_val(val: &ScVal) -> Result<Self, String> {
+        match val {
+            ScVal::U128(parts) => Ok(parts.into()),
+            val => mismatch("U128", val),
+        }
+    }
+}
+
+impl FromScVal for i128 {
+    fn from_sc_val(val: &ScVal) -> Result<Self, String> {
+        match val {
+            ScVal::I128(parts) => Ok(parts.into()),
+            val => mismatch("I128", val),
+        }
+    }
+}
+
+impl FromScVal for ScVal {
+    fn from_sc_val(val: &ScVal) -> Result<Self, String> {
+        Ok(val.clone())
+    }
+}
+
+impl FromScVal for ScAddress {
+    fn from_sc_val(val: &ScVal) -> Result<Self, String> {
+        match val {
+            ScVal::Address(address) => Ok(address.clone()),
+            val => mismatch("Address", val),
+        }
+    }
+}
+
+/// From a string, a symbol, or an address as a strkey
+impl FromScVal for String {
+    fn from_sc_val(val: &ScVal) -> Result<Self, String> {
+        match val {
+            ScVal::String(s) => Ok(s.0.to_utf8_string_lossy()),
+            ScVal::Symbol(s) => Ok(s.0.to_utf8_string_lossy()),
+            ScVal::Address(address) => Ok(address.to_string()),
+            val => mismatch("String, Symbol or Address", val),
+        }
+    }
+}
+
+impl FromScVal for Vec<u8> {
+    fn from_sc_val(val: &ScVal) -> Result<Self, String> {
+        match val {
+            ScVal::Bytes(bytes) => Ok(bytes.to_vec()),
+            val => mismatch("Bytes", val),
+        }
+    }
+}
+
+impl FromScVal for [u8; 32] {
+    fn from_sc_val(val: &ScVal) -> Result<Self, String> {
+        match val {
+            ScVal::Bytes(bytes) => bytes
+                .as_slice()
+                .try_into()
+                .map_err(|_| format!("expected 32 bytes, found {}", bytes.len())),
+            val => mismatch("Bytes", val),
+        }
+    }
+}
+
+/// `None` for [ScVal::Void] and missing map entries
+impl<T: FromScVal> FromScVal for Option<T> {
+    fn from_sc_val(val: &ScVal) -> Result<Self, String> {
+        match val {
+            ScVal::Void => Ok(None),
+            val => T::from_sc_val(val).map(Some),
+        }
+    }
+
+    fn missing() -> Result<Self, String> {
+        Ok(None)
+    }
+}
+
+/// From a vector of two elements
+impl<A: FromScVal, B: FromScVal> FromScVal for (A, B) {
+    fn from_sc_val(val: &ScVal) -> Result<Self, String> {
+        match val {
+            ScVal::Vec(Some(vec)) if vec.len() == 2 => Ok((
+                A::from_sc_val(&vec[0]).map_err(|reason| format!("element 0: {reason}"))?,
+                B::from_sc_val(&vec[1]).map_err(|reason| format!("element 1: {reason}"))?,
+            )),
+            ScVal::Vec(vec) => Err(format!(
+                "expected 2 elements, found {}",
+                vec.as_ref().map_or(0, |v| v.len())
+            )),
+            val => mismatch("Vec", val),
+        }
+    }
+}
+
+/// Decoder used by the code generated by `#[derive(FromContractEvent)]`
+#[doc(hidden)]
+pub struct EventDecoder<'a> {
+    target: &'static str,
+    topics: &'a [ScVal],
+    value: &'a ScVal,
+}
+
+impl<'a> EventDecoder<'a> {
+    pub fn new(target: &'static str, topics: &'a [ScVal], value: &'a ScVal) -> Self {
+        EventDecoder {
+            target,
+            topics,
+            value,
+        }
+    }
+
+    pub fn name(&self, name: &str) -> Result<(), Error> {
+        match self.topics.first() {
+            Some(ScVal::Symbol(s)) if s.as_slice() == name.as_bytes() => Ok(()),
+            Some(ScVal::Symbol(s)) => Err(self.error(format!(
+                "topic 0: expected `{name}`, found `{}`",
+                s.0.to_utf8_string_lossy()
+            ))),
+            Some(val) => Err(self.error(format!(
+                "topic 0: expected Symbol `{name}`, found {}",
+                val.discriminant().name()
+            ))),
+            None => Err(self.error("no topic".to_string())),
+        }
+    }
+
+    pub fn topic<T: FromScVal>(&self, index: usize) -> Result<T, Error> {
+        let topic = self.topics.get(index).ok_or_else(|| {
+            self.error(format!(
+                "expected at least {} topics, found {}",
+                index + 1,
+                self.topics.len()
+            ))
+        })?;
+        T::from_sc_val(topic).map_err(|reason| self.error(format!("topic {index}: {reason}")))
+    }
+
+    pub fn value<T: FromScVal>(&self) -> Result<T, Error> {
+        T::from_sc_val(self.value).map_err(|reason| self.error(format!("value: {reason}")))
+    }
+
+    pub fn value_entry<T: FromScVal>(&self, key: &str) -> Result<T, Error> {
+        let ScVal::Map(Some(map)) = self.value else {
+            return Err(self.error(format!(
+                "value: expected Map, found {}",
+                self.value.discriminant().name()
+            )));
+        };
+        let entry = map.iter().find(|e| match &e.key {
+            ScVal::Symbol(s) => s.as_slice() == key.as_bytes(),
+            ScVal::String(s) => s.as_slice() == key.as_bytes(),
+            _ => false,
+        });
+        match entry {
+            Some(entry) => T::from_sc_val(&entry.val),
+            None => T::missing(),
+        }
+        .map_err(|reason| self.error(format!("value `{key}`: {reason}")))
+    }
+
+    fn error(&self, reason: String) -> Error {
+        Error::InvalidEvent {
+            target: self.target,
+            reason,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Mutex;
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::soroban_rpc::EventType;
+    use crate::tests::event_json as event;
+    use crate::transport::{async_trait, RpcTransport};
+    use crate::Options;
+
+    // Pages served for each cursor, then the new events once tailing
+    struct Pages {
+        calls: Mutex<Vec<Value>>,
+    }
+
+    #[async_trait]
+    impl RpcTransport for Pages {
+        async fn call(&self, _method: &str, params: Value) -> Result<Value, Error> {
+            let calls = {
+                let mut calls = self.calls.lock().unwrap();
+                calls.push(params.clone());
+                calls.len()
+            };
+            let (events, cursor) = match params["pagination"]["cursor"].as_str() {
+                None => (vec![event(100, "1"), event(101, "2")], "2"),
+                Some("2") => (vec![event(101, "2"), event(102, "3")], "3"),
+                Some("3") if calls > 4 => (vec![event(105, "4")], "4"),
+                Some(cursor) => (vec![], cursor),
+            };
+            Ok(json!({
+                "events": events,
+                "cursor": cursor,
+                "latestLedger": 105,
+            }))
+        }
+    }
+
+    fn server() -> Server {
+        let pages = Pages {
+            calls: Mutex::new(Vec::new()),
+        };
+        Server::with_transport(pages, Options::default())
+    }
+
+    fn ids(events: Vec<Result<EventResponse, Error>>) -> Vec<String> {
+        events.into_iter().map(|e| e.unwrap().id).collect()
+    }
+
+    #[tokio::test]
+    async fn follow_cursor() {
+        let server = server();
+        let filters = vec![EventFilter::new(EventType::Contract)];
+        let events = server
+            .events_stream(Pagination::From(100), filters)
+            .limit(2)
+            .collect::<Vec<_>>()
+            .await;
+        // The duplicated event of the second page is skipped
+        assert_eq!(ids(events), ["1", "2", "3"]);
+    }
+
+    #[tokio::test]
+    async fn end_ledger() {
+        let server = server();
+        let events = server
+            .events_stream(Pagination::FromTo(100, 102), vec![])
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(ids(events), ["1", "2"]);
+    }
+
+    #[tokio::test]
+    async fn tail_new_ledgers() {
+        let server = server();
+        let events = server
+            .events_stream(Pagination::From(100), vec![])
+            .tail(Duration::from_millis(1))
+            .take(4)
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(ids(events), ["1", "2", "3", "4"]);
+    }
+
+    // Events of each contract of the filters, two per page
+    struct Split;
+
+    #[async_trait]
+    impl RpcTransport for Split {
+        async fn call(&self, _method: &str, params: Value) -> Result<Value, Error> {
+            let contracts: Vec<&str> = params["filters"]
+                .as_array()
+                .unwrap()
+                .iter()
+                .flat_map(|f| f["contractIds"].as_array().unwrap())
+                .map(|c| c.as_str().unwrap())
+                .collect();
+            let ledgers: &[u32] = match contracts.contains(&"C5") {
+                true => &[101, 104],
+                false => &[100, 102],
+            };
+            let events: Vec<Value> = ledgers
+                .iter()
+                .map(|l| event(*l, &format!("{l:010}-1")))
+                .collect();
+            Ok(json!({
+                "events": events,
+                "cursor": format!("{:010}-1", ledgers[1]),
+                "latestLedger": 110,
+            }))
+        }
+    }
+
+    fn contract_filters(count: usize) -> Vec<EventFilter> {
+        (0..count)
+            .map(|i| EventFilter::new(EventType::Contract).contract(&format!("C{i}")))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn filter_limits() {
+        let server = Server::with_transport(Split, Options::default());
+        let result = server
+            .get_events(Pagination::From(100), contract_filters(6), 2)
+            .await;
+        assert!(matches!(
+            result,
+            Err(Error::EventFilterLimit {
+                count: 6,
+                max: 5,
+                ..
+            })
+        ));
+
+        let filter = (0..6).fold(EventFilter::new(EventType::Contract), |f, i| {
+            f.contract(&format!("C{i}")).topic(vec![crate::Topic::Any])
+        });
+        assert_eq!(filter.split().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn fan_out() {
+        let options = Options {
+            fan_out_events: true,
+            ..Default::default()
+        };
+        let server = Server::with_transport(Split, options);
+        let response = server
+            .get_events(Pagination::From(100), contract_filters(6), 2)
+            .await
+            .unwrap();
+        let ledgers: Vec<u64> = response.events.iter().map(|e| e.ledger).collect();
+        // Ledger 102 is within the two first pages, the next page starts after ledger 101
+        assert_eq!(ledgers, [100, 101]);
+        assert_eq!(response.cursor.as_deref(), Some("0000000101-1"));
+
+        let response = server
+            .get_events(Pagination::From(100), contract_filters(6), 10)
+            .await
+            .unwrap();
+        let ledgers: Vec<u64> = response.events.iter().map(|e| e.ledger).collect();
+        // Ledger 104 is after the end of the first request
+        assert_eq!(ledgers, [100, 101, 102]);
+        assert_eq!(response.cursor.as_deref(), Some("0000000102-1"));
+    }
+
+    #[derive(Debug, PartialEq, FromContractEvent)]
+    #[event(name = "transfer")]
+    struct Transfer {
+        #[event(topic = 1)]
+        from: String,
+        #[event(topic = 2)]
+        to: ScAddress,
+        #[event(value = "amount")]
+        amount: i128,
+        #[event(value = "to_muxed_id")]
+        to_muxed_id: Option<u64>,
+    }
+
+    #[test]
+    fn derive_from_contract_event() {
+        use stellar_baselib::xdr::{ContractId, Hash, Int128Parts, ScMap, ScMapEntry};
+
+        let contract = ScAddress::Contract(ContractId(Hash([1; 32])));
+        let symbol = |s: &str| ScVal::Symbol(s.try_into().unwrap());
+        let topics = vec![
+            symbol("transfer"),
+            ScVal::Address(contract.clone()),
+            ScVal::Address(contract.clone()),
+        ];
+        let entry = |key: &str, val| ScMapEntry {
+            key: symbol(key),
+            val,
+        };
+        let amount = ScVal::I128(Int128Parts { hi: 0, lo: 100 });
+        let value = ScVal::Map(Some(ScMap(
+            vec![entry("amount", amount.clone())].try_into().unwrap(),
+        )));
+
+        let transfer = Transfer::from_contract_event(&topics, &value).unwrap();
+        assert_eq!(
+            transfer,
+            Transfer {
+                from: contract.to_string(),
+                to: contract.clone(),
+                amount: 100,
+                to_muxed_id: None,
+            }
+        );
+
+        let error = |topics: &[ScVal], value: &ScVal| {
+            Transfer::from_contract_event(topics, value)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error(&topics, &amount),
+            "InvalidEvent: cannot decode Transfer, value: expected Map, found I128"
+        );
+        assert_eq!(
+            error(&topics[..2], &value),
+            "InvalidEvent: cannot decode Transfer, expected at least 3 topics, found 2"
+        );
+        let mut mint = topics.clone();
+        mint[0] = symbol("mint");
+        assert_eq!(
+            error(&mint, &value),
+            "InvalidEvent: cannot decode Transfer, topic 0: expected `transfer`, found `mint`"
+        );
+        let mut invalid = topics.clone();
+        invalid[2] = ScVal::U32(1);
+        assert_eq!(
+            error(&invalid, &value),
+            "InvalidEvent: cannot decode Transfer, topic 2: expected Address, found U32"
+        );
+    }
+}
@@ -0,0 +1,452 @@
+use std::cmp::Reverse;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use futures::future::join_all;
+use serde_json::Value;
+
+use crate::error::{Error, InvalidRpcUrl};
+use crate::server::{handle_response, Options};
+use crate::soroban_rpc::{GetHealthResponse, GetNetworkResponse};
+use crate::time::Instant;
+use crate::transport::{async_trait, JsonRpc, RpcTransport};
+
+/// Configuration of a [Failover] transport
+#[derive(Debug, Clone)]
+pub struct FailoverOptions {
+    /// Maximum number of ledgers an endpoint can lag behind the most advanced one to be
+    /// preferred (default: 5)
+    pub max_ledger_lag: u32,
+    /// Interval between two health checks of the endpoints (default: 30s)
+    pub health_check_interval: Duration,
+}
+
+impl Default for FailoverOptions {
+    fn default() -> Self {
+        Self {
+            max_ledger_lag: 5,
+            health_check_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Status of an endpoint after a health check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EndpointStatus {
+    /// URL of the endpoint
+    pub url: String,
+    /// Response to [get_health](crate::Server::get_health), `None` if the endpoint did not reply
+    pub health: Option<GetHealthResponse>,
+    /// Network passphrase of the endpoint, once verified
+    pub passphrase: Option<String>,
+    /// Whether the endpoint serves another network, it is then never used nor checked again
+    pub rejected: bool,
+}
+
+/// [RpcTransport] routing requests to the healthiest of several RPC endpoints
+///
+/// The endpoints are checked with [get_health](crate::Server::get_health) every
+/// [FailoverOptions::health_check_interval]. Requests go to the healthy endpoint with the largest
+/// `ledger_retention_window` among the ones lagging at most [FailoverOptions::max_ledger_lag]
+/// ledgers, or else to the one with the lowest `latest_ledger` lag. When an endpoint fails
+/// with a network error, the request is sent to the next one.
+///
+/// All the endpoints must serve the same network: the passphrase returned by
+/// [get_network](crate::Server::get_network) is verified for each endpoint before it is used.
+/// An endpoint serving another network is rejected for the lifetime of the transport.
+///
+/// Use [Server::with_endpoints](crate::Server::with_endpoints) to build a [Server](crate::Server)
+/// with this transport.
+pub struct Failover {
+    endpoints: Vec<Endpoint>,
+    options: FailoverOptions,
+    state: Mutex<State>,
+    // Held while the endpoints are checked, so that concurrent requests run a single check
+    checking: tokio::sync::Mutex<()>,
+}
+
+struct Endpoint {
+    url: String,
+    client: JsonRpc,
+}
+
+#[derive(Default)]
+struct State {
+    statuses: Vec<EndpointStatus>,
+    down: Vec<bool>,
+    passphrase: Option<String>,
+    checked_at: Option<Instant>,
+}
+
+impl std::fmt::Debug for Failover {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Failover")
+            .field(
+                "endpoints",
+                &self.endpoints.iter().map(|e| &e.url).collect::<Vec<_>>(),
+            )
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Failover {
+    /// Create the transport for the RPC endpoints at `server_urls`
+    ///
+    /// Each endpoint uses a [JsonRpc] transport configured with `opts`, and
+    /// [Options::failover] configures the health checks.
+    pub fn new(server_urls: &[&str], opts: &Options) -> Result<Self, Error> {
+        if server_urls.is_empty() {
+            return Err(Error::InvalidRpc(InvalidRpcUrl::NoUrl));
+        }
+        let endpoints = server_urls
+            .iter()
+            .map(|url| {
+                Ok(Endpoint {
+                    url: url.to_string(),
+                    client: JsonRpc::with_options(url, opts)?,
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+        let state = State {
+            statuses: endpoints
+                .iter()
+                .map(|e| EndpointStatus {
+                    url: e.url.clone(),
+                    health: None,
+                    passphrase: None,
+                    rejected: false,
+                })
+                .collect(),
+            down: vec![false; endpoints.len()],
+            ..Default::default()
+        };
+
+        Ok(Failover {
+            endpoints,
+            options: opts.failover.clone(),
+            state: Mutex::new(state),
+            checking: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    /// Check the health of every endpoint, and verify the network of the new ones
+    ///
+    /// Returns [Error::NetworkMismatch] if an endpoint is found serving another network. This
+    /// check runs automatically, calling it is only needed to fail early.
+    pub async fn check_health(&self) -> Result<Vec<EndpointStatus>, Error> {
+        let _checking = self.checking.lock().await;
+        match self.check().await {
+            (_, Some(mismatch)) => Err(mismatch),
+            (statuses, None) => Ok(statuses),
+        }
+    }
+
+    /// Check the endpoints, returning their status and the first network mismatch found
+    async fn check(&self) -> (Vec<EndpointStatus>, Option<Error>) {
+        let statuses = {
+            let mut state = self.state.lock().unwrap();
+            state.checked_at = Some(Instant::now());
+            state.statuses.clone()
+        };
+
+        let checks = self
+            .endpoints
+            .iter()
+            .zip(statuses)
+            .map(|(endpoint, status)| async move {
+                if status.rejected {
+                    return (None, None);
+                }
+                let health = endpoint
+                    .client
+                    .call("getHealth", Value::Null)
+                    .await
+                    .and_then(handle_response::<GetHealthResponse>)
+                    .ok()
+                    .filter(|h| h.status == "healthy");
+                let passphrase = if status.passphrase.is_none() && health.is_some() {
+                    endpoint
+                        .client
+                        .call("getNetwork", Value::Null)
+                        .await
+                        .and_then(handle_response::<GetNetworkResponse>)
+                        .ok()
+                        .and_then(|n| n.passphrase)
+                } else {
+                    None
+                };
+                (health, passphrase)
+            });
+        let results = join_all(checks).await;
+
+        let mut state = self.state.lock().unwrap();
+        let mut mismatch = None;
+        for (i, (health, passphrase)) in results.into_iter().enumerate() {
+            if state.statuses[i].rejected {
+                continue;
+            }
+            if let Some(found) = passphrase {
+                match &state.passphrase {
+                    Some(expected) if *expected != found => {
+                        let endpoint = &self.endpoints[i].url;
+                        tracing::warn!(%endpoint, %expected, %found, "endpoint rejected");
+                        mismatch.get_or_insert(Error::NetworkMismatch {
+                            endpoint: endpoint.clone(),
+                            expected: expected.clone(),
+                            found,
+                        });
+                        state.statuses[i].rejected = true;
+                        state.down[i] = true;
+                        continue;
+                    }
+                    Some(_) => {}
+                    None => state.passphrase = Some(found.clone()),
+                }
+                state.statuses[i].passphrase = Some(found);
+            }
+            state.down[i] = health.is_none();
+            state.statuses[i].health = health;
+        }
+
+        (state.statuses.clone(), mismatch)
+    }
+
+    /// Indexes of the endpoints, ordered by preference
+    fn ranking(&self) -> Vec<usize> {
+        let state = self.state.lock().unwrap();
+        let most_recent = state
+            .statuses
+            .iter()
+            .filter_map(|s| s.health.as_ref().map(|h| h.latest_ledger))
+            .max()
+            .unwrap_or_default();
+
+        let mut ranking: Vec<usize> = (0..self.endpoints.len())
+            .filter(|i| state.statuses[*i].passphrase.is_some() && !state.statuses[*i].rejected)
+            .collect();
+        ranking.sort_by_key(|i| {
+            let status = &state.statuses[*i];
+            match &status.health {
+                Some(health) if !state.down[*i] => {
+                    let lag = most_recent.saturating_sub(health.latest_ledger);
+                    // The lags within max_ledger_lag are equally good
+                    let lagging = lag > self.options.max_ledger_lag;
+                    (
+                        false,
+                        lagging,
+                        if lagging { lag } else { 0 },
+                        Reverse(health.ledger_retention_window),
+                    )
+                }
+                _ => (true, true, u32::MAX, Reverse(0)),
+            }
+        });
+        ranking
+    }
+
+    fn expired(&self, since: Option<Instant>) -> bool {
+        let state = self.state.lock().unwrap();
+        match state.checked_at {
+            // Already checked by a concurrent request
+            at if at != since => false,
+            // Check again while no endpoint could be verified
+            Some(_) if state.passphrase.is_none() => true,
+            Some(at) => at.elapsed() >= self.options.health_check_interval,
+            None => true,
+        }
+    }
+
+    /// Check the endpoints if the last check expired
+    ///
+    /// A rejected endpoint is only reported by [Failover::check_health], requests fail with
+    /// [Error::NetworkMismatch] only if no endpoint could be verified.
+    async fn refresh(&self) -> Result<(), Error> {
+        let since = self.state.lock().unwrap().checked_at;
+        if !self.expired(since) {
+            return Ok(());
+        }
+        let _checking = self.checking.lock().await;
+        if !self.expired(since) {
+            return Ok(());
+        }
+        let (statuses, mismatch) = self.check().await;
+        let verified = statuses
+            .iter()
+            .any(|s| s.passphrase.is_some() && !s.rejected);
+        match mismatch {
+            Some(e) if !verified => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    fn set_down(&self, index: usize) {
+        self.state.lock().unwrap().down[index] = true;
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl RpcTransport for Failover {
+    async fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+        self.refresh().await?;
+        let mut last_error = None;
+        for i in self.ranking() {
+            match self.endpoints[i].client.call(method, params.clone()).await {
+                Err(e) if e.is_unavailable() => {
+                    self.set_down(i);
+                    last_error = Some(e);
+                }
+                result => return result,
+            }
+        }
+        Err(last_error.unwrap_or(Error::NoHealthyEndpoint))
+    }
+
+    async fn call_batch(
+        &self,
+        calls: Vec<(String, Value)>,
+    ) -> Result<Vec<Result<Value, Error>>, Error> {
+        self.refresh().await?;
+        let mut last_error = None;
+        for i in self.ranking() {
+            match self.endpoints[i].client.call_batch(calls.clone()).await {
+                Err(e) if e.is_unavailable() => {
+                    self.set_down(i);
+                    last_error = Some(e);
+                }
+                result => return result,
+            }
+        }
+        Err(last_error.unwrap_or(Error::NoHealthyEndpoint))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use wiremock::matchers::{body_partial_json, method};
+    use wiremock::{Mock, MockServer};
+
+    use super::*;
+    use crate::tests::EchoId;
+
+    async fn endpoint(passphrase: &str, latest_ledger: u32, retention: u32) -> MockServer {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "getHealth"})))
+            .respond_with(EchoId::new(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "status": "healthy",
+                    "latestLedger": latest_ledger,
+                    "oldestLedger": latest_ledger.saturating_sub(retention) + 1,
+                    "ledgerRetentionWindow": retention
+                }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "getNetwork"})))
+            .respond_with(EchoId::new(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {"passphrase": passphrase, "protocolVersion": 22}
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("POST"))
+            .and(body_partial_json(json!({"method": "getLatestLedger"})))
+            .respond_with(EchoId::new(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {"id": "", "protocolVersion": 22, "sequence": latest_ledger}
+            })))
+            .mount(&server)
+            .await;
+        server
+    }
+
+    fn options() -> Options {
+        Options {
+            allow_http: true,
+            retry: crate::RetryPolicy::none(),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn route_to_healthiest() {
+        let lagging = endpoint("Test", 100, 1000).await;
+        let small = endpoint("Test", 198, 100).await;
+        let best = endpoint("Test", 200, 1000).await;
+        let failover =
+            Failover::new(&[&lagging.uri(), &small.uri(), &best.uri()], &options()).unwrap();
+
+        let statuses = failover.check_health().await.unwrap();
+        assert!(statuses
+            .iter()
+            .all(|s| s.passphrase.as_deref() == Some("Test")));
+        assert_eq!(failover.ranking(), vec![2, 1, 0]);
+
+        let latest = failover.call("getLatestLedger", Value::Null).await.unwrap();
+        assert_eq!(latest["sequence"], 200);
+    }
+
+    #[tokio::test]
+    async fn prefer_retention_within_max_lag() {
+        let small = endpoint("Test", 200, 100).await;
+        let large = endpoint("Test", 198, 1000).await;
+        let failover = Failover::new(&[&small.uri(), &large.uri()], &options()).unwrap();
+        failover.check_health().await.unwrap();
+
+        // Lagging 2 ledgers is within the default max_ledger_lag
+        assert_eq!(failover.ranking(), vec![1, 0]);
+    }
+
+    #[tokio::test]
+    async fn fail_over_on_unavailable_endpoint() {
+        let best = endpoint("Test", 200, 1000).await;
+        let backup = endpoint("Test", 199, 1000).await;
+        let failover = Failover::new(&[&best.uri(), &backup.uri()], &options()).unwrap();
+        failover.check_health().await.unwrap();
+
+        best.reset().await;
+        let latest = failover.call("getLatestLedger", Value::Null).await.unwrap();
+        assert_eq!(latest["sequence"], 199);
+        assert_eq!(failover.ranking(), vec![1, 0]);
+    }
+
+    #[tokio::test]
+    async fn network_mismatch() {
+        let testnet = endpoint("Test", 200, 1000).await;
+        let mainnet = endpoint("Public", 200, 1000).await;
+        let failover = Failover::new(&[&testnet.uri(), &mainnet.uri()], &options()).unwrap();
+
+        let result = failover.check_health().await;
+        assert!(matches!(result, Err(Error::NetworkMismatch { .. })));
+        // Only the verified endpoint is used
+        assert_eq!(failover.ranking(), vec![0]);
+
+        // The rejected endpoint does not fail the requests
+        let latest = failover.call("getLatestLedger", Value::Null).await.unwrap();
+        assert_eq!(latest["sequence"], 200);
+        let statuses = failover.check_health().await.unwrap();
+        assert!(statuses[1].rejected);
+        assert_eq!(mainnet.received_requests().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn single_concurrent_check() {
+        let server = endpoint("Test", 200, 1000).await;
+        let failover = Failover::new(&[&server.uri()], &options()).unwrap();
+
+        let calls = (0..5).map(|_| failover.call("getLatestLedger", Value::Null));
+        for result in join_all(calls).await {
+            assert_eq!(result.unwrap()["sequence"], 200);
+        }
+        // One getHealth and one getNetwork, then the five calls
+        assert_eq!(server.received_requests().await.unwrap().len(), 7);
+    }
+}
@@ -0,0 +1,691 @@
+use std::fmt;
+
+use stellar_baselib::xdr::{
+    ContractEventBody, ContractId, DiagnosticEvent, ExtendFootprintTtlResultCode, Hash,
+    InvokeHostFunctionResultCode, OperationResult, OperationResultTr, RestoreFootprintResultCode,
+    ScError, ScErrorCode, ScErrorType, ScVal, TransactionResult, TransactionResultCode,
+};
+
+/// Why a transaction failed, decoded from its [TransactionResult]
+///
+/// Returned by [SendTransactionResponse::try_to_failure](crate::soroban_rpc::SendTransactionResponse::try_to_failure)
+/// and [TransactionDetails::try_to_failure](crate::soroban_rpc::TransactionDetails::try_to_failure).
+/// The [Display](fmt::Display) implementation explains the cause.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionFailure {
+    /// One or more operations failed (tx_failed)
+    OperationsFailed(Vec<OperationFailure>),
+    /// The inner transaction of a fee bump transaction failed (tx_fee_bump_inner_failed)
+    FeeBumpInnerFailed(Box<TransactionFailure>),
+    /// The ledger closed before the minimum time bound (tx_too_early)
+    TooEarly,
+    /// The ledger closed after the maximum time bound (tx_too_late)
+    TooLate,
+    /// The transaction has no operation (tx_missing_operation)
+    MissingOperation,
+    /// The sequence number is not the next one of the source account (tx_bad_seq)
+    BadSeq,
+    /// Missing or invalid signatures (tx_bad_auth)
+    BadAuth,
+    /// The fee would bring the source account below its reserve (tx_insufficient_balance)
+    InsufficientBalance,
+    /// The source account does not exist (tx_no_account)
+    NoAccount,
+    /// The fee is lower than the network requires (tx_insufficient_fee)
+    InsufficientFee {
+        /// Fee charged, in stroops
+        fee_charged: i64,
+    },
+    /// The transaction has more signatures than needed (tx_bad_auth_extra)
+    BadAuthExtra,
+    /// Unknown error of stellar-core (tx_internal_error)
+    InternalError,
+    /// The transaction type is not supported (tx_not_supported)
+    NotSupported,
+    /// A sponsorship is not confirmed (tx_bad_sponsorship)
+    BadSponsorship,
+    /// The minimum sequence age or ledger gap precondition is not met (tx_bad_min_seq_age_or_gap)
+    BadMinSeqAgeOrGap,
+    /// The preconditions are invalid (tx_malformed)
+    Malformed,
+    /// The Soroban resources or fees of the transaction are invalid (tx_soroban_invalid)
+    SorobanInvalid,
+    /// Any other transaction code
+    Other(TransactionResultCode),
+}
+
+impl TransactionFailure {
+    /// Decode why the transaction of `result` failed, `None` if it succeeded
+    pub fn from_result(result: &TransactionResult) -> Option<Self> {
+        use stellar_baselib::xdr::TransactionResultResult as R;
+
+        match &result.result {
+            R::TxSuccess(_) | R::TxFeeBumpInnerSuccess(_) => None,
+            R::TxFailed(ops) => Some(Self::from_operations(ops)),
+            R::TxFeeBumpInnerFailed(pair) => {
+                use stellar_baselib::xdr::InnerTransactionResultResult as I;
+
+                let inner = &pair.result;
+                let failure = match &inner.result {
+                    I::TxSuccess(_) => return None,
+                    I::TxFailed(ops) => Self::from_operations(ops),
+                    r => Self::from_code(r.discriminant(), inner.fee_charged),
+                };
+                Some(Self::FeeBumpInnerFailed(Box::new(failure)))
+            }
+            r => Some(Self::from_code(r.discriminant(), result.fee_charged)),
+        }
+    }
+
+    /// Failures of the operations, empty if the transaction failed before they were applied
+    pub fn operations(&self) -> &[OperationFailure] {
+        match self {
+            Self::OperationsFailed(ops) => ops,
+            Self::FeeBumpInnerFailed(inner) => inner.operations(),
+            _ => &[],
+        }
+    }
+
+    fn from_operations(ops: &[OperationResult]) -> Self {
+        Self::OperationsFailed(
+            ops.iter()
+                .enumerate()
+                .filter_map(|(index, op)| {
+                    OperationReason::from_result(op)
+                        .map(|reason| OperationFailure { index, reason })
+                })
+                .collect(),
+        )
+    }
+
+    fn from_code(code: TransactionResultCode, fee_charged: i64) -> Self {
+        use TransactionResultCode as C;
+
+        match code {
+            C::TxTooEarly => Self::TooEarly,
+            C::TxTooLate => Self::TooLate,
+            C::TxMissingOperation => Self::MissingOperation,
+            C::TxBadSeq => Self::BadSeq,
+            C::TxBadAuth => Self::BadAuth,
+            C::TxInsufficientBalance => Self::InsufficientBalance,
+            C::TxNoAccount => Self::NoAccount,
+            C::TxInsufficientFee => Self::InsufficientFee { fee_charged },
+            C::TxBadAuthExtra => Self::BadAuthExtra,
+            C::TxInternalError => Self::InternalError,
+            C::TxNotSupported => Self::NotSupported,
+            C::TxBadSponsorship => Self::BadSponsorship,
+            C::TxBadMinSeqAgeOrGap => Self::BadMinSeqAgeOrGap,
+            C::TxMalformed => Self::Malformed,
+            C::TxSorobanInvalid => Self::SorobanInvalid,
+            code => Self::Other(code),
+        }
+    }
+}
+
+impl fmt::Display for TransactionFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::OperationsFailed(ops) => {
+                write!(f, "transaction failed")?;
+                for (i, op) in ops.iter().enumerate() {
+                    write!(f, "{} {op}", if i == 0 { ":" } else { ";" })?;
+                }
+                Ok(())
+            }
+            Self::FeeBumpInnerFailed(inner) => {
+                write!(f, "inner transaction of the fee bump failed: {inner}")
+            }
+            Self::TooEarly => write!(f, "the ledger closed before the minimum time bound"),
+            Self::TooLate => write!(f, "the ledger closed after the maximum time bound"),
+            Self::MissingOperation => write!(f, "the transaction has no operation"),
+            Self::BadSeq => write!(
+                f,
+                "bad sequence number, it must be the sequence number of the source account + 1"
+            ),
+            Self::BadAuth => write!(f, "missing or invalid signatures"),
+            Self::InsufficientBalance => write!(
+                f,
+                "the fee would bring the source account balance below its reserve"
+            ),
+            Self::NoAccount => write!(f, "the source account does not exist"),
+            Self::InsufficientFee { fee_charged } => write!(
+                f,
+                "the fee is too low for the network ({fee_charged} stroops charged), increase it"
+            ),
+            Self::BadAuthExtra => write!(f, "the transaction has unused signatures"),
+            Self::InternalError => write!(f, "internal error of stellar-core"),
+            Self::NotSupported => write!(f, "the transaction type is not supported"),
+            Self::BadSponsorship => write!(f, "a sponsorship is not confirmed"),
+            Self::BadMinSeqAgeOrGap => write!(
+                f,
+                "the minimum sequence age or ledger gap precondition is not met"
+            ),
+            Self::Malformed => write!(f, "the preconditions of the transaction are invalid"),
+            Self::SorobanInvalid => write!(
+                f,
+                "the Soroban resources or fees are invalid, simulate the transaction again"
+            ),
+            Self::Other(code) => write!(f, "transaction failed with code {}", code.name()),
+        }
+    }
+}
+
+impl std::error::Error for TransactionFailure {}
+
+/// Failure of an operation of a transaction, see [TransactionFailure::OperationsFailed]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OperationFailure {
+    /// Index of the operation in the transaction
+    pub index: usize,
+    /// Why the operation failed
+    pub reason: OperationReason,
+}
+
+impl fmt::Display for OperationFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "operation {} {}", self.index, self.reason)
+    }
+}
+
+/// Why an operation failed, see [OperationFailure]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OperationReason {
+    /// Missing or invalid signatures for the operation (op_bad_auth)
+    BadAuth,
+    /// The source account of the operation does not exist (op_no_account)
+    NoAccount,
+    /// The operation is not supported (op_not_supported)
+    NotSupported,
+    /// The account has too many subentries (op_too_many_subentries)
+    TooManySubentries,
+    /// The operation did too much work (op_exceeded_work_limit)
+    ExceededWorkLimit,
+    /// The account is sponsoring too many entries (op_too_many_sponsoring)
+    TooManySponsoring,
+    /// The contract call failed
+    InvokeHostFunction(InvokeHostFunctionResultCode),
+    /// The TTL extension failed
+    ExtendFootprintTtl(ExtendFootprintTtlResultCode),
+    /// The restoration failed
+    RestoreFootprint(RestoreFootprintResultCode),
+    /// Any other operation failed
+    Other {
+        /// Type of the operation, such as `Payment`
+        operation: &'static str,
+        /// Result code of the operation, such as `Underfunded`
+        code: &'static str,
+    },
+}
+
+impl OperationReason {
+    /// Decode why the operation of `result` failed, `None` if it succeeded
+    pub fn from_result(result: &OperationResult) -> Option<Self> {
+        let tr = match result {
+            OperationResult::OpInner(tr) => tr,
+            OperationResult::OpBadAuth => return Some(Self::BadAuth),
+            OperationResult::OpNoAccount => return Some(Self::NoAccount),
+            OperationResult::OpNotSupported => return Some(Self::NotSupported),
+            OperationResult::OpTooManySubentries => return Some(Self::TooManySubentries),
+            OperationResult::OpExceededWorkLimit => return Some(Self::ExceededWorkLimit),
+            OperationResult::OpTooManySponsoring => return Some(Self::TooManySponsoring),
+        };
+        match tr {
+            OperationResultTr::InvokeHostFunction(r) => match r.discriminant() {
+                InvokeHostFunctionResultCode::Success => None,
+                code => Some(Self::InvokeHostFunction(code)),
+            },
+            OperationResultTr::ExtendFootprintTtl(r) => match r.discriminant() {
+                ExtendFootprintTtlResultCode::Success => None,
+                code => Some(Self::ExtendFootprintTtl(code)),
+            },
+            OperationResultTr::RestoreFootprint(r) => match r.discriminant() {
+                RestoreFootprintResultCode::Success => None,
+                code => Some(Self::RestoreFootprint(code)),
+            },
+            tr => match result_code(tr) {
+                "Success" => None,
+                code => Some(Self::Other {
+                    operation: tr.name(),
+                    code,
+                }),
+            },
+        }
+    }
+}
+
+impl fmt::Display for OperationReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        use InvokeHostFunctionResultCode as Invoke;
+
+        match self {
+            Self::BadAuth => write!(f, "has missing or invalid signatures"),
+            Self::NoAccount => write!(f, "has a source account that does not exist"),
+            Self::NotSupported => write!(f, "is not supported"),
+            Self::TooManySubentries => write!(f, "failed, the account has too many subentries"),
+            Self::ExceededWorkLimit => write!(f, "exceeded the work limit"),
+            Self::TooManySponsoring => {
+                write!(f, "failed, the account is sponsoring too many entries")
+            }
+            Self::InvokeHostFunction(code) => match code {
+                Invoke::Trapped => write!(
+                    f,
+                    "trapped: the contract panicked or returned an error, see the diagnostic events"
+                ),
+                Invoke::ResourceLimitExceeded => write!(
+                    f,
+                    "exceeded the resources declared in the transaction, simulate it again"
+                ),
+                Invoke::EntryArchived => write!(
+                    f,
+                    "accessed an archived ledger entry, restore it before calling the contract"
+                ),
+                Invoke::InsufficientRefundableFee => write!(
+                    f,
+                    "has a refundable fee too low for the rent and the events"
+                ),
+                Invoke::Malformed => write!(f, "is a malformed contract call"),
+                code => write!(f, "failed with InvokeHostFunction {}", code.name()),
+            },
+            Self::ExtendFootprintTtl(code) => footprint_reason(f, code.name()),
+            Self::RestoreFootprint(code) => footprint_reason(f, code.name()),
+            Self::Other { operation, code } => write!(f, "failed with {operation} {code}"),
+        }
+    }
+}
+
+// ExtendFootprintTtl and RestoreFootprint share the same result codes
+fn footprint_reason(f: &mut fmt::Formatter<'_>, code: &str) -> fmt::Result {
+    match code {
+        "ResourceLimitExceeded" => write!(
+            f,
+            "exceeded the resources declared in the transaction, simulate it again"
+        ),
+        "InsufficientRefundableFee" => write!(f, "has a refundable fee too low for the rent"),
+        "Malformed" => write!(f, "is malformed"),
+        code => write!(f, "failed with {code}"),
+    }
+}
+
+// Name of the result code of an operation, "Success" if it succeeded
+fn result_code(tr: &OperationResultTr) -> &'static str {
+    use OperationResultTr as T;
+
+    match tr {
+        T::CreateAccount(r) => r.name(),
+        T::Payment(r) => r.name(),
+        T::PathPaymentStrictReceive(r) => r.name(),
+        T::ManageSellOffer(r) => r.name(),
+        T::CreatePassiveSellOffer(r) => r.name(),
+        T::SetOptions(r) => r.name(),
+        T::ChangeTrust(r) => r.name(),
+        T::AllowTrust(r) => r.name(),
+        T::AccountMerge(r) => r.name(),
+        T::Inflation(r) => r.name(),
+        T::ManageData(r) => r.name(),
+        T::BumpSequence(r) => r.name(),
+        T::ManageBuyOffer(r) => r.name(),
+        T::PathPaymentStrictSend(r) => r.name(),
+        T::CreateClaimableBalance(r) => r.name(),
+        T::ClaimClaimableBalance(r) => r.name(),
+        T::BeginSponsoringFutureReserves(r) => r.name(),
+        T::EndSponsoringFutureReserves(r) => r.name(),
+        T::RevokeSponsorship(r) => r.name(),
+        T::Clawback(r) => r.name(),
+        T::ClawbackClaimableBalance(r) => r.name(),
+        T::SetTrustLineFlags(r) => r.name(),
+        T::LiquidityPoolDeposit(r) => r.name(),
+        T::LiquidityPoolWithdraw(r) => r.name(),
+        T::InvokeHostFunction(r) => r.name(),
+        T::ExtendFootprintTtl(r) => r.name(),
+        T::RestoreFootprint(r) => r.name(),
+    }
+}
+
+/// Why a contract call failed, extracted from the diagnostic events
+///
+/// The diagnostic events of a failed simulation or transaction contain the calls made between
+/// contracts and the error raised by the host or the contract. Returned by
+/// [SimulateTransactionResponse::try_to_contract_failure](crate::soroban_rpc::SimulateTransactionResponse::try_to_contract_failure),
+/// [SendTransactionResponse::try_to_contract_failure](crate::soroban_rpc::SendTransactionResponse::try_to_contract_failure)
+/// and [TransactionDetails::try_to_contract_failure](crate::soroban_rpc::TransactionDetails::try_to_contract_failure).
+/// The diagnostic events are only present when they are enabled on the RPC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractFailure {
+    /// The error raised, the first one reported by the events
+    pub error: ScError,
+    /// Message of the error, if any
+    pub message: Option<String>,
+    /// Address of the contract executing when the error was raised, if any
+    pub contract: Option<String>,
+    /// Calls between contracts at the time of the error, outermost first
+    pub call_stack: Vec<ContractCall>,
+}
+
+/// Call of a contract function, see [ContractFailure::call_stack]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractCall {
+    /// Address of the contract
+    pub contract: String,
+    /// Name of the function
+    pub function: String,
+}
+
+impl ContractFailure {
+    /// Extract the failure from the diagnostic `events`, `None` if no error was reported
+    pub fn from_events(events: &[DiagnosticEvent]) -> Option<Self> {
+        let mut call_stack: Vec<ContractCall> = Vec::new();
+        for event in events {
+            let ContractEventBody::V0(body) = &event.event.body;
+            match body.topics.as_slice() {
+                [ScVal::Symbol(name), ScVal::Bytes(id), ScVal::Symbol(function), ..]
+                    if name.as_slice() == b"fn_call" =>
+                {
+                    let contract = <[u8; 32]>::try_from(id.as_slice())
+                        .map(|id| ContractId(Hash(id)).to_string())
+                        .unwrap_or_default();
+                    call_stack.push(ContractCall {
+                        contract,
+                        function: function.0.to_utf8_string_lossy(),
+                    });
+                }
+                [ScVal::Symbol(name), ..] if name.as_slice() == b"fn_return" => {
+                    call_stack.pop();
+                }
+                [ScVal::Symbol(name), ScVal::Error(error), ..] if name.as_slice() == b"error" => {
+                    let contract = event
+                        .event
+                        .contract_id
+                        .as_ref()
+                        .map(ToString::to_string)
+                        .or_else(|| call_stack.last().map(|c| c.contract.clone()));
+                    return Some(ContractFailure {
+                        error: error.clone(),
+                        message: error_message(&body.data),
+                        contract,
+                        call_stack,
+                    });
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+
+    /// Code of the error returned by the contract, `None` for host errors
+    pub fn contract_code(&self) -> Option<u32> {
+        match self.error {
+            ScError::Contract(code) => Some(code),
+            _ => None,
+        }
+    }
+
+    /// Type and code of the error raised by the host, `None` for contract errors
+    pub fn host_error(&self) -> Option<(ScErrorType, ScErrorCode)> {
+        let code = match &self.error {
+            ScError::Contract(_) => return None,
+            ScError::WasmVm(code)
+            | ScError::Context(code)
+            | ScError::Storage(code)
+            | ScError::Object(code)
+            | ScError::Crypto(code)
+            | ScError::Events(code)
+            | ScError::Budget(code)
+            | ScError::Value(code)
+            | ScError::Auth(code) => *code,
+        };
+        Some((self.error.discriminant(), code))
+    }
+}
+
+impl fmt::Display for ContractFailure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.host_error() {
+            Some((kind, code)) => write!(f, "host error {}/{}", kind.name(), code.name())?,
+            None => write!(
+                f,
+                "contract error #{}",
+                self.contract_code().unwrap_or_default()
+            )?,
+        }
+        if let Some(contract) = &self.contract {
+            write!(f, " in contract {contract}")?;
+        }
+        if let Some(message) = &self.message {
+            write!(f, ": {message}")?;
+        }
+        for (i, call) in self.call_stack.iter().enumerate() {
+            let separator = if i == 0 { ", calls: " } else { " -> " };
+            write!(f, "{separator}{}::{}", call.contract, call.function)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ContractFailure {}
+
+// The data of an error event is the message, or the message followed by its arguments
+fn error_message(data: &ScVal) -> Option<String> {
+    match data {
+        ScVal::String(s) => Some(s.0.to_utf8_string_lossy()),
+        ScVal::Vec(Some(values)) => match values.first() {
+            Some(ScVal::String(s)) => Some(s.0.to_utf8_string_lossy()),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use stellar_baselib::xdr::{
+        ContractEvent, ContractEventType, ContractEventV0, ExtensionPoint, InnerTransactionResult,
+        InnerTransactionResultExt, InnerTransactionResultPair, InnerTransactionResultResult,
+        InvokeHostFunctionResult, Limits, PaymentResult, ScString, TransactionResultExt,
+        TransactionResultResult, WriteXdr,
+    };
+
+    use super::*;
+    use crate::soroban_rpc::SendTransactionResponse;
+
+    fn result(result: TransactionResultResult) -> TransactionResult {
+        TransactionResult {
+            fee_charged: 100,
+            result,
+            ext: TransactionResultExt::V0,
+        }
+    }
+
+    #[test]
+    fn transaction_codes() {
+        assert_eq!(
+            TransactionFailure::from_result(&result(TransactionResultResult::TxSuccess(
+                vec![].try_into().unwrap()
+            ))),
+            None
+        );
+        let failure =
+            TransactionFailure::from_result(&result(TransactionResultResult::TxInsufficientFee))
+                .unwrap();
+        assert_eq!(
+            failure,
+            TransactionFailure::InsufficientFee { fee_charged: 100 }
+        );
+        assert!(failure.to_string().contains("100 stroops"));
+        assert!(failure.operations().is_empty());
+    }
+
+    #[test]
+    fn operation_failures() {
+        let ops = vec![
+            OperationResult::OpInner(OperationResultTr::Payment(PaymentResult::Success)),
+            OperationResult::OpInner(OperationResultTr::InvokeHostFunction(
+                InvokeHostFunctionResult::Trapped,
+            )),
+            OperationResult::OpInner(OperationResultTr::Payment(PaymentResult::Underfunded)),
+        ];
+        let failure = TransactionFailure::from_result(&result(TransactionResultResult::TxFailed(
+            ops.try_into().unwrap(),
+        )))
+        .unwrap();
+        assert_eq!(
+            failure.operations(),
+            [
+                OperationFailure {
+                    index: 1,
+                    reason: OperationReason::InvokeHostFunction(
+                        InvokeHostFunctionResultCode::Trapped
+                    ),
+                },
+                OperationFailure {
+                    index: 2,
+                    reason: OperationReason::Other {
+                        operation: "Payment",
+                        code: "Underfunded",
+                    },
+                },
+            ]
+        );
+        let message = failure.to_string();
+        assert!(message.contains("operation 1 trapped"), "{message}");
+        assert!(message.contains("operation 2 failed with Payment Underfunded"));
+    }
+
+    #[test]
+    fn fee_bump_inner_failure() {
+        let inner = InnerTransactionResultPair {
+            transaction_hash: Hash([0; 32]),
+            result: InnerTransactionResult {
+                fee_charged: 100,
+                result: InnerTransactionResultResult::TxBadSeq,
+                ext: InnerTransactionResultExt::V0,
+            },
+        };
+        let failure = TransactionFailure::from_result(&result(
+            TransactionResultResult::TxFeeBumpInnerFailed(inner),
+        ))
+        .unwrap();
+        assert_eq!(
+            failure,
+            TransactionFailure::FeeBumpInnerFailed(Box::new(TransactionFailure::BadSeq))
+        );
+    }
+
+    #[test]
+    fn send_transaction_failure() {
+        let xdr = result(TransactionResultResult::TxBadSeq)
+            .to_xdr_base64(Limits::none())
+            .unwrap();
+        let response: SendTransactionResponse = serde_json::from_value(serde_json::json!({
+            "status": "ERROR",
+            "hash": "05870e35fc94e5424f72d125959760b5f60631d91452bde2d11126fb5044e35d",
+            "latestLedger": 100,
+            "latestLedgerCloseTime": "1700000000",
+            "errorResultXdr": xdr
+        }))
+        .unwrap();
+        assert_eq!(
+            response.try_to_failure().unwrap(),
+            Some(TransactionFailure::BadSeq)
+        );
+
+        // A corrupt result is an error, not a missing failure
+        let response: SendTransactionResponse = serde_json::from_value(serde_json::json!({
+            "status": "ERROR",
+            "hash": "05870e35fc94e5424f72d125959760b5f60631d91452bde2d11126fb5044e35d",
+            "latestLedger": 100,
+            "latestLedgerCloseTime": "1700000000",
+            "errorResultXdr": "AAAA"
+        }))
+        .unwrap();
+        assert!(response.try_to_failure().is_err());
+    }
+
+    fn event(contract: Option<[u8; 32]>, topics: Vec<ScVal>, data: ScVal) -> DiagnosticEvent {
+        DiagnosticEvent {
+            in_successful_contract_call: false,
+            event: ContractEvent {
+                ext: ExtensionPoint::V0,
+                contract_id: contract.map(|id| ContractId(Hash(id))),
+                type_: ContractEventType::Diagnostic,
+                body: ContractEventBody::V0(ContractEventV0 {
+                    topics: topics.try_into().unwrap(),
+                    data,
+                }),
+            },
+        }
+    }
+
+    fn symbol(s: &str) -> ScVal {
+        ScVal::Symbol(s.try_into().unwrap())
+    }
+
+    fn string(s: &str) -> ScVal {
+        ScVal::String(ScString(s.try_into().unwrap()))
+    }
+
+    fn fn_call(contract: [u8; 32], function: &str) -> DiagnosticEvent {
+        let id = ScVal::Bytes(contract.to_vec().try_into().unwrap());
+        event(
+            None,
+            vec![symbol("fn_call"), id, symbol(function)],
+            ScVal::Void,
+        )
+    }
+
+    #[test]
+    fn contract_failure() {
+        let token = ContractId(Hash([2; 32])).to_string();
+        let events = vec![
+            fn_call([1; 32], "swap"),
+            fn_call([3; 32], "decimals"),
+            event(
+                Some([3; 32]),
+                vec![symbol("fn_return"), symbol("decimals")],
+                ScVal::U32(7),
+            ),
+            fn_call([2; 32], "transfer"),
+            event(
+                Some([2; 32]),
+                vec![symbol("error"), ScVal::Error(ScError::Contract(10))],
+                ScVal::Vec(Some(
+                    vec![string("balance is not sufficient")]
+                        .try_into()
+                        .unwrap(),
+                )),
+            ),
+            event(
+                Some([1; 32]),
+                vec![
+                    symbol("error"),
+                    ScVal::Error(ScError::WasmVm(ScErrorCode::InvalidAction)),
+                ],
+                string("escalating error"),
+            ),
+        ];
+
+        let failure = ContractFailure::from_events(&events).unwrap();
+        assert_eq!(failure.contract_code(), Some(10));
+        assert_eq!(failure.host_error(), None);
+        assert_eq!(failure.contract.as_deref(), Some(token.as_str()));
+        assert_eq!(
+            failure.message.as_deref(),
+            Some("balance is not sufficient")
+        );
+        let calls: Vec<&str> = failure
+            .call_stack
+            .iter()
+            .map(|c| c.function.as_str())
+            .collect();
+        assert_eq!(calls, ["swap", "transfer"]);
+        assert!(failure
+            .to_string()
+            .starts_with(&format!("contract error #10 in contract {token}: balance")));
+
+        assert_eq!(ContractFailure::from_events(&events[..4]), None);
+        let host = ContractFailure::from_events(&events[5..]).unwrap();
+        assert_eq!(
+            host.host_error(),
+            Some((ScErrorType::WasmVm, ScErrorCode::InvalidAction))
+        );
+    }
+}
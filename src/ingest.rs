@@ -0,0 +1,533 @@
+#[cfg(not(target_arch = "wasm32"))]
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::error::{Error, RpcErrorCode};
+use crate::soroban_rpc::{EventResponse, GetEventsResponse};
+use crate::time::sleep;
+use crate::transport::async_trait;
+use crate::{EventFilter, Pagination, Server};
+
+/// Error returned by an [EventSink], reported as [Error::EventSink]
+pub type SinkError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Storage of the cursor of the last processed events, see [Ingester]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait CheckpointStore: Send + Sync {
+    /// Cursor saved last, `None` if no cursor was saved
+    async fn load(&self) -> Result<Option<String>, Error>;
+
+    /// Save the `cursor` of the last processed events
+    async fn save(&self, cursor: &str) -> Result<(), Error>;
+}
+
+/// [CheckpointStore] keeping the cursor in memory, lost when the process stops
+#[derive(Debug, Default)]
+pub struct MemoryCheckpoint {
+    cursor: Mutex<Option<String>>,
+}
+
+impl MemoryCheckpoint {
+    /// Store starting with `cursor`, if any
+    pub fn new(cursor: Option<String>) -> Self {
+        MemoryCheckpoint {
+            cursor: Mutex::new(cursor),
+        }
+    }
+
+    /// Cursor saved last
+    pub fn cursor(&self) -> Option<String> {
+        self.cursor.lock().unwrap().clone()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl CheckpointStore for MemoryCheckpoint {
+    async fn load(&self) -> Result<Option<String>, Error> {
+        Ok(self.cursor())
+    }
+
+    async fn save(&self, cursor: &str) -> Result<(), Error> {
+        *self.cursor.lock().unwrap() = Some(cursor.to_string());
+        Ok(())
+    }
+}
+
+/// [CheckpointStore] keeping the cursor in a file, not available on wasm32
+///
+/// The new cursor is written and synced to a temporary file which then replaces the file, so it
+/// is never left half written, even on a power loss. The file is read and written on the
+/// blocking thread pool of tokio.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug)]
+pub struct FileCheckpoint {
+    path: PathBuf,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl FileCheckpoint {
+    /// Store the cursor in the file at `path`, created on the first save
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileCheckpoint { path: path.into() }
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl CheckpointStore for FileCheckpoint {
+    async fn load(&self) -> Result<Option<String>, Error> {
+        let path = self.path.clone();
+        match spawn_blocking(move || std::fs::read_to_string(path)).await? {
+            Ok(cursor) if cursor.trim().is_empty() => Ok(None),
+            Ok(cursor) => Ok(Some(cursor.trim().to_string())),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn save(&self, cursor: &str) -> Result<(), Error> {
+        use std::io::Write;
+
+        let path = self.path.clone();
+        let cursor = cursor.to_string();
+        spawn_blocking(move || {
+            let mut tmp = path.clone().into_os_string();
+            tmp.push(".tmp");
+            let mut file = std::fs::File::create(&tmp)?;
+            file.write_all(cursor.as_bytes())?;
+            file.sync_all()?;
+            std::fs::rename(&tmp, &path)
+        })
+        .await??;
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+async fn spawn_blocking<T: Send + 'static>(
+    f: impl FnOnce() -> T + Send + 'static,
+) -> Result<T, Error> {
+    tokio::task::spawn_blocking(f)
+        .await
+        .map_err(|e| Error::UnexpectedError {
+            operation: "accessing the checkpoint file",
+            reason: e.to_string(),
+        })
+}
+
+/// Events missed by an [Ingester], because the RPC does not retain them anymore
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gap {
+    /// First ledger whose events may be missing
+    pub from_ledger: u32,
+    /// Oldest ledger retained by the RPC, where the ingestion continues
+    pub oldest_ledger: u32,
+}
+
+/// Destination of the events processed by an [Ingester]
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait EventSink: Send + Sync {
+    /// Process a page of `events`
+    ///
+    /// The checkpoint is saved once this returns `Ok`. If it fails, or if the process stops
+    /// before the checkpoint is saved, the same events are delivered again by the next run.
+    async fn handle(&self, events: Vec<EventResponse>) -> Result<(), SinkError>;
+
+    /// Called when the checkpoint is older than the oldest ledger retained by the RPC, before
+    /// continuing from the oldest ledger. Return an error to stop the ingestion instead.
+    async fn gap(&self, _gap: Gap) -> Result<(), SinkError> {
+        Ok(())
+    }
+}
+
+/// Configuration of an [Ingester]
+#[derive(Debug, Clone)]
+pub struct IngestOptions {
+    /// Ledger to start from when there is no checkpoint, the oldest ledger retained by the RPC
+    /// if `None` (default: `None`)
+    pub start_ledger: Option<u32>,
+    /// Maximum number of events per page, the RPC default if `None` (default: `None`)
+    pub limit: Option<u32>,
+    /// Interval between two requests once the latest ledger is reached (default: 5s)
+    pub poll_interval: Duration,
+}
+
+impl Default for IngestOptions {
+    fn default() -> Self {
+        Self {
+            start_ledger: None,
+            limit: None,
+            poll_interval: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Runner delivering the events matching some filters to an [EventSink]
+///
+/// The cursor of the processed events is saved in a [CheckpointStore], and the ingestion
+/// resumes from it after a restart. Events are delivered at least once: a page is delivered
+/// again when the checkpoint could not be saved after it was processed.
+///
+/// # Example
+/// ```rust,no_run
+/// # use soroban_client::*;
+/// # use soroban_client::error::Error;
+/// # use soroban_client::soroban_rpc::*;
+/// use soroban_client::ingest::*;
+///
+/// struct Print;
+///
+/// #[soroban_client::transport::async_trait]
+/// impl EventSink for Print {
+///     async fn handle(&self, events: Vec<EventResponse>) -> Result<(), SinkError> {
+///         for event in events {
+///             println!("{} {}", event.ledger, event.id);
+///         }
+///         Ok(())
+///     }
+/// }
+///
+/// # async fn ingest() -> Result<(), Error> {
+/// let server = Server::new("https://soroban-testnet.stellar.org", Options::default())?;
+/// let filter = EventFilter::new(EventType::Contract).contract("CAA...");
+/// let ingester = Ingester::new(
+///     &server,
+///     vec![filter],
+///     FileCheckpoint::new("events.cursor"),
+///     Print,
+///     IngestOptions::default(),
+/// );
+/// ingester.run().await
+/// # }
+/// ```
+pub struct Ingester<'a, C, S> {
+    server: &'a Server,
+    filters: Vec<EventFilter>,
+    checkpoint: C,
+    sink: S,
+    options: IngestOptions,
+}
+
+impl<C, S> std::fmt::Debug for Ingester<'_, C, S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Ingester")
+            .field("filters", &self.filters)
+            .field("options", &self.options)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<'a, C: CheckpointStore, S: EventSink> Ingester<'a, C, S> {
+    /// Create the runner delivering the events of `server` matching `filters` to `sink`
+    pub fn new(
+        server: &'a Server,
+        filters: Vec<EventFilter>,
+        checkpoint: C,
+        sink: S,
+        options: IngestOptions,
+    ) -> Self {
+        Ingester {
+            server,
+            filters,
+            checkpoint,
+            sink,
+            options,
+        }
+    }
+
+    /// The checkpoint store
+    pub fn checkpoint(&self) -> &C {
+        &self.checkpoint
+    }
+
+    /// The event sink
+    pub fn sink(&self) -> &S {
+        &self.sink
+    }
+
+    /// Deliver the events until an error occurs, polling for new events every
+    /// [IngestOptions::poll_interval] once the latest ledger is reached
+    ///
+    /// After an error, the ingestion can be restarted and resumes from the checkpoint.
+    pub async fn run(&self) -> Result<(), Error> {
+        let mut next = self.start().await?;
+        loop {
+            if self.step(&mut next).await? == 0 {
+                sleep(self.options.poll_interval).await;
+            }
+        }
+    }
+
+    /// Deliver the events up to the latest ledger, and return the number of events delivered
+    pub async fn catch_up(&self) -> Result<usize, Error> {
+        let mut next = self.start().await?;
+        let mut delivered = 0;
+        loop {
+            match self.step(&mut next).await? {
+                0 => return Ok(delivered),
+                n => delivered += n,
+            }
+        }
+    }
+
+    async fn start(&self) -> Result<Pagination, Error> {
+        if let Some(cursor) = self.checkpoint.load().await? {
+            return Ok(Pagination::Cursor(cursor));
+        }
+        let ledger = match self.options.start_ledger {
+            Some(ledger) => ledger,
+            None => self.server.get_health().await?.oldest_ledger,
+        };
+        Ok(Pagination::From(ledger))
+    }
+
+    // Deliver the next page and save its cursor, returns the number of events delivered
+    async fn step(&self, next: &mut Pagination) -> Result<usize, Error> {
+        let response = self.fetch(next).await?;
+        let cursor = response
+            .cursor
+            .clone()
+            .or_else(|| response.events.last().map(|e| e.id.clone()));
+        let delivered = response.events.len();
+        if delivered > 0 {
+            self.sink
+                .handle(response.events)
+                .await
+                .map_err(Error::EventSink)?;
+        }
+        if let Some(cursor) = cursor {
+            self.checkpoint.save(&cursor).await?;
+            *next = Pagination::Cursor(cursor);
+        }
+        Ok(delivered)
+    }
+
+    // Fetch the page at `next`, moving it to the oldest ledger after a gap
+    async fn fetch(&self, next: &mut Pagination) -> Result<GetEventsResponse, Error> {
+        let from_ledger = match next {
+            Pagination::From(ledger) | Pagination::FromTo(ledger, _) => Some(*ledger),
+            Pagination::Cursor(cursor) => cursor_ledger(cursor),
+        };
+        let response = match self.get_events(next).await {
+            Err(e) if e.rpc_code() == Some(RpcErrorCode::StartLedgerOutOfRange) => {
+                let oldest_ledger = self.server.get_health().await?.oldest_ledger;
+                match from_ledger {
+                    Some(from_ledger) if from_ledger >= oldest_ledger => return Err(e),
+                    _ => {}
+                }
+                self.report_gap(from_ledger.unwrap_or_default(), oldest_ledger)
+                    .await?;
+                *next = Pagination::From(oldest_ledger);
+                return self.get_events(next).await;
+            }
+            response => response?,
+        };
+
+        let oldest_ledger = response.oldest_ledger.map(|l| l as u32);
+        if let (Some(from_ledger), Some(oldest_ledger)) = (from_ledger, oldest_ledger) {
+            if from_ledger < oldest_ledger {
+                self.report_gap(from_ledger, oldest_ledger).await?;
+            }
+        }
+        Ok(response)
+    }
+
+    async fn get_events(&self, next: &Pagination) -> Result<GetEventsResponse, Error> {
+        self.server
+            .get_events(next.clone(), self.filters.clone(), self.options.limit)
+            .await
+    }
+
+    async fn report_gap(&self, from_ledger: u32, oldest_ledger: u32) -> Result<(), Error> {
+        let gap = Gap {
+            from_ledger,
+            oldest_ledger,
+        };
+        tracing::warn!(from_ledger, oldest_ledger, "events missed by the ingestion");
+        self.sink.gap(gap).await.map_err(Error::EventSink)
+    }
+}
+
+// Ledger of an event id or a cursor, the first part is a TOID with the ledger in the 32 high bits
+fn cursor_ledger(cursor: &str) -> Option<u32> {
+    let toid: u64 = cursor.split('-').next()?.parse().ok()?;
+    Some((toid >> 32) as u32)
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use serde_json::{json, Value};
+
+    use super::*;
+    use crate::tests::event_json;
+    use crate::transport::RpcTransport;
+    use crate::Options;
+
+    fn id(ledger: u32) -> String {
+        format!("{:019}-0000000001", u64::from(ledger) << 32)
+    }
+
+    fn event(ledger: u32) -> Value {
+        event_json(ledger, &id(ledger))
+    }
+
+    // RPC retaining the events from ledger 100 to 102
+    struct Rpc;
+
+    #[async_trait]
+    impl RpcTransport for Rpc {
+        async fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+            if method == "getHealth" {
+                return Ok(json!({
+                    "status": "healthy",
+                    "latestLedger": 102,
+                    "oldestLedger": 100,
+                    "ledgerRetentionWindow": 3
+                }));
+            }
+            let from = match params["pagination"]["cursor"].as_str() {
+                Some(cursor) => cursor_ledger(cursor).unwrap() + 1,
+                None => params["startLedger"].as_u64().unwrap() as u32,
+            };
+            if from < 100 && params["startLedger"].is_u64() {
+                return Err(Error::RPCError {
+                    code: -32600,
+                    message: "startLedger must be between the oldest ledger: 100 and the latest ledger: 102".into(),
+                    data: None,
+                    request_id: None,
+                });
+            }
+            let events: Vec<Value> = (from.max(100)..=102).take(2).map(event).collect();
+            let cursor = events
+                .last()
+                .map_or(id(102), |e| e["id"].as_str().unwrap().into());
+            Ok(json!({
+                "events": events,
+                "cursor": cursor,
+                "latestLedger": 102,
+                "oldestLedger": 100
+            }))
+        }
+    }
+
+    #[derive(Default)]
+    struct Sink {
+        ledgers: Mutex<Vec<u64>>,
+        gaps: Mutex<Vec<Gap>>,
+        fail: AtomicBool,
+    }
+
+    #[async_trait]
+    impl EventSink for Sink {
+        async fn handle(&self, events: Vec<EventResponse>) -> Result<(), SinkError> {
+            if self.fail.swap(false, Ordering::SeqCst) {
+                return Err("database unavailable".into());
+            }
+            let mut ledgers = self.ledgers.lock().unwrap();
+            ledgers.extend(events.iter().map(|e| e.ledger));
+            Ok(())
+        }
+
+        async fn gap(&self, gap: Gap) -> Result<(), SinkError> {
+            self.gaps.lock().unwrap().push(gap);
+            Ok(())
+        }
+    }
+
+    fn options(start_ledger: u32) -> IngestOptions {
+        IngestOptions {
+            start_ledger: Some(start_ledger),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn deliver_and_checkpoint() {
+        let server = Server::with_transport(Rpc, Options::default());
+        let ingester = Ingester::new(
+            &server,
+            vec![],
+            MemoryCheckpoint::default(),
+            Sink::default(),
+            options(100),
+        );
+        assert_eq!(ingester.catch_up().await.unwrap(), 3);
+        assert_eq!(*ingester.sink().ledgers.lock().unwrap(), [100, 101, 102]);
+        assert_eq!(ingester.checkpoint().cursor(), Some(id(102)));
+        assert!(ingester.sink().gaps.lock().unwrap().is_empty());
+
+        // Resumes from the checkpoint
+        assert_eq!(ingester.catch_up().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn redeliver_after_sink_error() {
+        let server = Server::with_transport(Rpc, Options::default());
+        let sink = Sink::default();
+        sink.fail.store(true, Ordering::SeqCst);
+        let ingester = Ingester::new(
+            &server,
+            vec![],
+            MemoryCheckpoint::default(),
+            sink,
+            options(100),
+        );
+        assert!(matches!(
+            ingester.catch_up().await,
+            Err(Error::EventSink(_))
+        ));
+        assert_eq!(ingester.checkpoint().cursor(), None);
+
+        assert_eq!(ingester.catch_up().await.unwrap(), 3);
+        assert_eq!(*ingester.sink().ledgers.lock().unwrap(), [100, 101, 102]);
+    }
+
+    #[tokio::test]
+    async fn report_gaps() {
+        let server = Server::with_transport(Rpc, Options::default());
+        let expected = |from_ledger| Gap {
+            from_ledger,
+            oldest_ledger: 100,
+        };
+
+        // Start ledger rejected by the RPC
+        let ingester = Ingester::new(
+            &server,
+            vec![],
+            MemoryCheckpoint::default(),
+            Sink::default(),
+            options(50),
+        );
+        assert_eq!(ingester.catch_up().await.unwrap(), 3);
+        assert_eq!(*ingester.sink().gaps.lock().unwrap(), [expected(50)]);
+
+        // Checkpoint older than the oldest ledger
+        let ingester = Ingester::new(
+            &server,
+            vec![],
+            MemoryCheckpoint::new(Some(id(60))),
+            Sink::default(),
+            options(50),
+        );
+        assert_eq!(ingester.catch_up().await.unwrap(), 3);
+        assert_eq!(*ingester.sink().gaps.lock().unwrap(), [expected(60)]);
+    }
+
+    #[tokio::test]
+    async fn file_checkpoint() {
+        let path = std::env::temp_dir().join(format!("checkpoint-{}", std::process::id()));
+        let checkpoint = FileCheckpoint::new(&path);
+        assert_eq!(checkpoint.load().await.unwrap(), None);
+        checkpoint.save(&id(100)).await.unwrap();
+        checkpoint.save(&id(101)).await.unwrap();
+        let loaded = FileCheckpoint::new(&path).load().await.unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, Some(id(101)));
+    }
+}
@@ -1,20 +1,82 @@
-use crate::error::Error::{JsonError, NetworkError};
-use futures::TryFutureExt;
+use crate::breaker::Breaker;
+use crate::error::Error::{JsonError, NetworkError, RateLimited, RequestTimeout};
+use crate::error::{InvalidRpcUrl, PARSING_BATCH_RESPONSE, PARSING_RESPONSE};
+use crate::limiter::Limiter;
+use crate::middleware::{MiddlewareChain, OutgoingRequest, ReceivedResponse};
+use crate::server::{Options, RetryPolicy};
+use crate::soroban_rpc::GetHealthResponse;
+use crate::time::{sleep, Instant};
+use crate::transport::RpcTransport;
+use async_trait::async_trait;
 use reqwest::{
-    header::{HeaderMap, HeaderName, HeaderValue},
-    Client, ClientBuilder, Url,
+    header::{HeaderMap, HeaderName, HeaderValue, RETRY_AFTER},
+    Client, ClientBuilder, RequestBuilder, StatusCode, Url,
 };
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use std::{collections::HashMap, time::Duration};
+use serde_json::Value;
+use std::{
+    str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
+    time::Duration,
+};
+use tracing::Instrument;
 
+/// Default [RpcTransport], sending JSON-RPC requests over HTTP with `reqwest`
 #[derive(Debug)]
 pub struct JsonRpc {
     client: Client,
+    headers: HeaderMap,
+    timeout: Duration,
     server_url: Url,
+    retry: RetryPolicy,
+    limiter: Limiter,
+    middleware: MiddlewareChain,
+    breaker: Option<Breaker>,
+    next_id: AtomicU64,
 }
 
 impl JsonRpc {
-    pub fn new(server_url: reqwest::Url, timeout: u64, headers: HashMap<String, String>) -> Self {
+    /// Create the transport for the RPC at `server_url`
+    ///
+    /// The `server_url` must use the https scheme unless [Options::allow_http] is set. The
+    /// [Options::timeout], [Options::headers], [Options::retry], [Options::rate_limit] and
+    /// [Options::max_in_flight] are used for every request, which goes through the
+    /// [Options::middleware] chain and the optional [Options::circuit_breaker]. The
+    /// [Options::http_client] is used if provided.
+    pub fn with_options(server_url: &str, opts: &Options) -> Result<Self, crate::error::Error> {
+        let server_url = Url::from_str(server_url).map_err(|source| {
+            crate::error::Error::InvalidRpc(InvalidRpcUrl::InvalidUri {
+                url: server_url.to_string(),
+                source,
+            })
+        })?;
+        let allow_http = opts.allow_http;
+        match server_url.scheme() {
+            "https" => {
+                // good
+            }
+            "http" if allow_http => {
+                // good
+            }
+            "http" if !allow_http => {
+                return Err(crate::error::Error::InvalidRpc(
+                    InvalidRpcUrl::UnsecureHttpNotAllowed,
+                ));
+            }
+            _ => {
+                return Err(crate::error::Error::InvalidRpc(
+                    InvalidRpcUrl::NotHttpScheme,
+                ));
+            }
+        };
+
+        JsonRpc::new(server_url, opts)
+    }
+
+    pub(crate) fn new(
+        server_url: reqwest::Url,
+        opts: &Options,
+    ) -> Result<Self, crate::error::Error> {
         let mut http_headers = HeaderMap::new();
         http_headers.insert(
             "X-Client-Name",
@@ -22,55 +84,352 @@ impl JsonRpc {
         );
         http_headers.insert("X-Client-Version", HeaderValue::from_static(crate::VERSION));
 
-        for (key, value) in headers {
+        for (key, value) in opts.headers.iter() {
             if let Ok(header_name) = HeaderName::try_from(key) {
                 let header_value =
-                    HeaderValue::from_str(&value).unwrap_or_else(|_| HeaderValue::from_static(""));
+                    HeaderValue::from_str(value).unwrap_or_else(|_| HeaderValue::from_static(""));
 
                 http_headers.insert(header_name, header_value);
             }
         }
 
-        let client = ClientBuilder::new()
-            .timeout(Duration::from_secs(timeout))
-            .default_headers(http_headers)
-            .build()
-            .expect("Cannot build http client");
-        JsonRpc { client, server_url }
+        let client = match &opts.http_client {
+            Some(client) => client.clone(),
+            None => ClientBuilder::new().build()?,
+        };
+        Ok(JsonRpc {
+            client,
+            // Applied to each request, so they are also used with a client from the options
+            headers: http_headers,
+            timeout: Duration::from_secs(opts.timeout),
+            server_url,
+            retry: opts.retry.clone(),
+            limiter: Limiter::new(opts.rate_limit.as_ref(), opts.max_in_flight),
+            middleware: opts.middleware.clone(),
+            breaker: opts.circuit_breaker.as_ref().map(Breaker::new),
+            next_id: AtomicU64::new(1),
+        })
     }
-    pub async fn post<P: Serialize, R: DeserializeOwned>(
+
+    pub(crate) async fn post<P: Serialize, R: DeserializeOwned>(
         &self,
         method: &str,
         params: P,
     ) -> Result<Response<R>, crate::error::Error> {
-        let url = self.server_url.clone();
-        let method = method.to_string();
-        let res = self
-            .client
-            .post(url)
-            .json(&Request {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        // Fields of the span opened by the Server, see telemetry::rpc_span
+        tracing::Span::current()
+            .record("endpoint", self.server_url.as_str())
+            .record("request_id", id);
+        let span = tracing::debug_span!("rpc_request", method, id);
+        async {
+            // Serialized once so every attempt sends the exact same body
+            let body = serde_json::to_vec(&Request {
+                jsonrpc: "2.0".to_string(),
+                id,
+                method: method.to_string(),
+                params,
+            })
+            .map_err(|source| JsonError {
+                operation: "serializing the request",
+                input: method.to_string(),
+                source,
+                request_id: Some(id),
+            })?;
+
+            let mut attempt = 1;
+            loop {
+                let text = self.send_retry(method, &body, &mut attempt).await?;
+                let mut parsed =
+                    serde_json::from_str::<Response<R>>(&text).map_err(|source| JsonError {
+                        operation: PARSING_RESPONSE,
+                        input: text,
+                        source,
+                        request_id: Some(id),
+                    })?;
+                parsed.check_id(id)?;
+                // Keep the id of the request when the RPC answers with a null id
+                parsed.id = Some(id);
+
+                match &parsed.error {
+                    Some(e)
+                        if attempt < self.retry.max_attempts
+                            && self.retry.retry_rpc_codes.contains(&e.code) =>
+                    {
+                        sleep(self.retry.delay(attempt)).await;
+                        attempt += 1;
+                    }
+                    Some(e) => {
+                        tracing::debug!(code = e.code, "RPC error");
+                        return Ok(parsed);
+                    }
+                    None => return Ok(parsed),
+                }
+            }
+        }
+        .instrument(span)
+        .await
+        .map_err(|e: crate::error::Error| e.with_request_id(id))
+    }
+
+    /// Send the `calls` as a single JSON-RPC batch, returning the responses in the same order
+    pub(crate) async fn post_batch(
+        &self,
+        calls: Vec<(String, Value)>,
+    ) -> Result<Vec<Response<Value>>, crate::error::Error> {
+        let first_id = self
+            .next_id
+            .fetch_add(calls.len() as u64, Ordering::Relaxed);
+        let requests: Vec<Request<Value>> = calls
+            .into_iter()
+            .zip(first_id..)
+            .map(|((method, params), id)| Request {
                 jsonrpc: "2.0".to_string(),
-                id: 1,
+                id,
                 method,
                 params,
             })
-            .send()
-            .map_err(NetworkError)
-            .await?;
-
-        let text = res.text().map_err(NetworkError).await?;
-        match serde_json::from_str::<Response<R>>(&text) {
-            Ok(parsed) => Ok(parsed),
-            Err(_e) => Err(JsonError(text.to_string())),
+            .collect();
+        let body = serde_json::to_vec(&requests).map_err(|source| JsonError {
+            operation: "serializing the request",
+            input: "batch".to_string(),
+            source,
+            request_id: None,
+        })?;
+
+        let mut attempt = 1;
+        let text = self.send_retry("batch", &body, &mut attempt).await?;
+        let mut responses = match serde_json::from_str::<Vec<Response<Value>>>(&text) {
+            Ok(responses) => responses,
+            Err(source) => {
+                // The whole batch can be rejected with a single error object
+                return match serde_json::from_str::<BatchError>(&text) {
+                    Ok(BatchError { error }) => Err(crate::error::Error::RPCError {
+                        code: error.code,
+                        message: error.message.unwrap_or_default(),
+                        data: error.data,
+                        request_id: None,
+                    }),
+                    Err(_) => Err(JsonError {
+                        operation: PARSING_BATCH_RESPONSE,
+                        input: text,
+                        source,
+                        request_id: None,
+                    }),
+                };
+            }
+        };
+
+        // Responses can be sent in any order, match them back with their request id
+        requests
+            .iter()
+            .map(|r| {
+                responses
+                    .iter()
+                    .position(|response| response.id == Some(r.id))
+                    .map(|i| responses.swap_remove(i))
+                    .ok_or_else(|| crate::error::Error::UnexpectedError {
+                        operation: PARSING_BATCH_RESPONSE,
+                        reason: format!("no response with id {} in the batch", r.id),
+                    })
+            })
+            .collect()
+    }
+
+    // Fail fast while the circuit is open, or probe the endpoint when it can be closed
+    async fn check_breaker(&self) -> Result<(), crate::error::Error> {
+        let Some(breaker) = &self.breaker else {
+            return Ok(());
+        };
+        if breaker.acquire()? {
+            let probe = self
+                .post::<_, GetHealthResponse>("getHealth", Value::Null)
+                .await;
+            let healthy = matches!(
+                &probe,
+                Ok(Response { result: Some(health), .. }) if health.status == "healthy"
+            );
+            breaker.record(healthy);
+            if !healthy {
+                return Err(crate::error::Error::CircuitOpen {
+                    retry_after: breaker.reset_timeout(),
+                });
+            }
         }
-        //res.json().map_err(NetworkError).await
+        Ok(())
+    }
+
+    fn record_breaker<T>(&self, result: &Result<T, crate::error::Error>) {
+        if let Some(breaker) = &self.breaker {
+            breaker.record(!matches!(result, Err(e) if e.is_unavailable()));
+        }
+    }
+
+    /// Send `body`, retrying on transient network failures and HTTP statuses
+    async fn send_retry(
+        &self,
+        method: &str,
+        body: &[u8],
+        attempt: &mut u32,
+    ) -> Result<String, crate::error::Error> {
+        loop {
+            let can_retry = *attempt < self.retry.max_attempts;
+            let mut delay = self.retry.delay(*attempt);
+            match self.send(method, body, *attempt).await {
+                Ok(sent)
+                    if can_retry && self.retry.retry_statuses.contains(&sent.status.as_u16()) =>
+                {
+                    if let Some(retry_after) = sent.retry_after {
+                        delay = std::cmp::max(delay, retry_after);
+                    }
+                }
+                Ok(sent) if sent.status == StatusCode::TOO_MANY_REQUESTS => {
+                    return Err(RateLimited {
+                        retry_after: sent.retry_after,
+                    })
+                }
+                Ok(sent) => return Ok(sent.text),
+                Err(NetworkError { source, .. }) if can_retry && is_transient(&source) => {}
+                Err(RequestTimeout(_)) if can_retry => {}
+                Err(e) => return Err(e),
+            }
+            sleep(delay).await;
+            *attempt += 1;
+        }
+    }
+
+    async fn send(
+        &self,
+        method: &str,
+        body: &[u8],
+        attempt: u32,
+    ) -> Result<Sent, crate::error::Error> {
+        let mut request = OutgoingRequest {
+            method: method.to_string(),
+            headers: HeaderMap::new(),
+            body: body.to_vec(),
+        };
+        self.middleware.before_send(&mut request).await?;
+
+        let _permit = self.limiter.acquire().await;
+        let start = Instant::now();
+        let builder = self
+            .client
+            .post(self.server_url.clone())
+            .headers(self.headers.clone())
+            .header("Content-Type", "application/json")
+            .headers(request.headers)
+            .body(request.body);
+        let res = self.execute(builder).await?;
+        let status = res.status();
+        let retry_after = res
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| {
+                let now = crate::time::SystemTime::now()
+                    .duration_since(crate::time::UNIX_EPOCH)
+                    .ok()?;
+                parse_retry_after(v, now)
+            });
+        let text = res.text().await?;
+
+        self.middleware
+            .after_receive(&ReceivedResponse {
+                method,
+                attempt,
+                status,
+                elapsed: start.elapsed(),
+                text: &text,
+            })
+            .await;
+        Ok(Sent {
+            status,
+            retry_after,
+            text,
+        })
+    }
+}
+
+impl JsonRpc {
+    #[cfg(not(target_arch = "wasm32"))]
+    async fn execute(
+        &self,
+        builder: RequestBuilder,
+    ) -> Result<reqwest::Response, crate::error::Error> {
+        Ok(builder.timeout(self.timeout).send().await?)
+    }
+
+    // fetch has no timeout, the request is raced with a timer
+    #[cfg(target_arch = "wasm32")]
+    async fn execute(
+        &self,
+        builder: RequestBuilder,
+    ) -> Result<reqwest::Response, crate::error::Error> {
+        crate::time::timeout(self.timeout, builder.send())
+            .await
+            .ok_or(RequestTimeout(self.timeout))?
+            .map_err(crate::error::Error::from)
     }
 }
 
+// Delay of a `Retry-After` header, in seconds or as an HTTP-date, with `now` the time since the
+// Unix epoch. A date in the past is no delay.
+fn parse_retry_after(value: &str, now: Duration) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+    let date = httpdate::parse_http_date(value)
+        .ok()?
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()?;
+    Some(date.saturating_sub(now))
+}
+
+// HTTP response to a request
+struct Sent {
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    text: String,
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl RpcTransport for JsonRpc {
+    async fn call(&self, method: &str, params: Value) -> Result<Value, crate::error::Error> {
+        self.check_breaker().await?;
+        let response = self.post::<_, Value>(method, params).await;
+        self.record_breaker(&response);
+        response?.into_result()
+    }
+
+    async fn call_batch(
+        &self,
+        calls: Vec<(String, Value)>,
+    ) -> Result<Vec<Result<Value, crate::error::Error>>, crate::error::Error> {
+        self.check_breaker().await?;
+        let responses = self.post_batch(calls).await;
+        self.record_breaker(&responses);
+        Ok(responses?.into_iter().map(Response::into_result).collect())
+    }
+}
+
+// Failures where the request may not have reached the RPC, or the response was lost
+#[cfg(not(target_arch = "wasm32"))]
+fn is_transient(e: &reqwest::Error) -> bool {
+    e.is_connect() || e.is_timeout() || e.is_request() || e.is_body()
+}
+
+#[cfg(target_arch = "wasm32")]
+fn is_transient(e: &reqwest::Error) -> bool {
+    e.is_timeout() || e.is_request() || e.is_body()
+}
+
 #[derive(Debug, Serialize)]
 pub struct Request<T> {
     jsonrpc: String,
-    id: i32,
+    id: u64,
     method: String,
     params: T,
 }
@@ -79,17 +438,52 @@ pub struct Request<T> {
 #[allow(dead_code)]
 pub struct Response<T> {
     jsonrpc: String,
-    id: i32,
+    id: Option<u64>,
     pub result: Option<T>,
     pub error: Option<Error>,
 }
 
+impl<T> Response<T> {
+    // The id is null only when the RPC could not read the request
+    fn check_id(&self, expected: u64) -> Result<(), crate::error::Error> {
+        match self.id {
+            Some(id) if id == expected => Ok(()),
+            None if self.error.is_some() => Ok(()),
+            found => Err(crate::error::Error::ResponseIdMismatch { expected, found }),
+        }
+    }
+}
+
+impl Response<Value> {
+    fn into_result(self) -> Result<Value, crate::error::Error> {
+        if let Some(result) = self.result {
+            Ok(result)
+        } else if let Some(error) = self.error {
+            Err(crate::error::Error::RPCError {
+                code: error.code,
+                message: error.message.unwrap_or_default(),
+                data: error.data,
+                request_id: self.id,
+            })
+        } else {
+            Err(crate::error::Error::UnexpectedError {
+                operation: PARSING_RESPONSE,
+                reason: format!("response {:?} without result nor error", self.id),
+            })
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct BatchError {
+    error: Error,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct Error {
-    #[allow(dead_code)]
     pub code: i32,
-    #[allow(dead_code)]
     pub message: Option<String>,
+    pub data: Option<Value>,
 }
 
 #[cfg(test)]
@@ -97,6 +491,7 @@ mod test {
 
     use std::collections::HashMap;
     use std::str::FromStr;
+    use std::time::Duration;
 
     use reqwest::Url;
     use serde::Deserialize;
@@ -109,8 +504,17 @@ mod test {
     use wiremock::MockServer;
     use wiremock::ResponseTemplate;
 
+    use crate::error::Error::{CircuitOpen, JsonError, RateLimited, ResponseIdMismatch};
+    use crate::error::{Error, RpcErrorCode};
+    use crate::jsonrpc::parse_retry_after;
     use crate::jsonrpc::JsonRpc;
     use crate::jsonrpc::Response;
+    use crate::tests::EchoId;
+    use crate::transport::RpcTransport;
+    use crate::CircuitBreaker;
+    use crate::Options;
+    use crate::RateLimit;
+    use crate::RetryPolicy;
 
     #[derive(Debug, Deserialize, PartialEq, Eq)]
     struct Data {
@@ -156,7 +560,11 @@ mod test {
         let server_url = Url::from_str(&mock_server.uri()).unwrap();
         let mut headers: HashMap<String, String> = HashMap::new();
         headers.insert("x-api-key".into(), "9864920430304".into());
-        let rpc = JsonRpc::new(server_url, 10, headers);
+        let opts = Options {
+            headers,
+            ..Default::default()
+        };
+        let rpc = JsonRpc::new(server_url, &opts).unwrap();
 
         let params = json!({
                 "number": 3,
@@ -165,7 +573,7 @@ mod test {
         });
 
         let response: Response<Data> = rpc.post("echo", params).await.unwrap();
-        assert_eq!(response.id, 1);
+        assert_eq!(response.id, Some(1));
         assert_eq!(response.jsonrpc, "2.0");
         assert_eq!(
             response.result,
@@ -176,4 +584,377 @@ mod test {
             })
         );
     }
+
+    fn options(retry: RetryPolicy) -> Options {
+        Options {
+            retry,
+            ..Default::default()
+        }
+    }
+
+    fn fast_retry(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            retry_rpc_codes: vec![-32603],
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn retry_on_transient_status() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": 42})),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let server_url = Url::from_str(&mock_server.uri()).unwrap();
+        let rpc = JsonRpc::new(server_url, &options(fast_retry(3))).unwrap();
+
+        let response: Response<u32> = rpc.post("echo", json!(null)).await.unwrap();
+        assert_eq!(response.result, Some(42));
+    }
+
+    #[tokio::test]
+    async fn retry_on_rpc_code() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(
+                json!({"jsonrpc": "2.0", "id": 1, "error": {"code": -32603, "message": "busy"}}),
+            ))
+            .up_to_n_times(1)
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"jsonrpc": "2.0", "id": 1, "result": 42})),
+            )
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let server_url = Url::from_str(&mock_server.uri()).unwrap();
+        let rpc = JsonRpc::new(server_url, &options(fast_retry(2))).unwrap();
+
+        let response: Response<u32> = rpc.post("echo", json!(null)).await.unwrap();
+        assert_eq!(response.result, Some(42));
+    }
+
+    #[tokio::test]
+    async fn no_retry() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("unavailable"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let server_url = Url::from_str(&mock_server.uri()).unwrap();
+        let rpc = JsonRpc::new(server_url, &options(RetryPolicy::none())).unwrap();
+
+        let response = rpc.post::<_, u32>("echo", json!(null)).await;
+        assert!(matches!(
+            &response,
+            Err(JsonError { input, request_id: Some(1), .. }) if input == "unavailable"
+        ));
+        assert!(response.unwrap_err().is_retryable());
+
+        // Decoding a valid result fails the same way on every endpoint
+        let decoding = crate::server::handle_response::<u32>(json!("not a number")).unwrap_err();
+        assert!(matches!(decoding, JsonError { .. }));
+        assert!(!decoding.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn rpc_error_code() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(EchoId::new(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                // Message of stellar-rpc v23.0.0, methods/get_events.go
+                "error": {
+                    "code": -32600,
+                    "message": "startLedger must be between the oldest ledger: 100 and the latest ledger: 200 for this rpc instance.",
+                    "data": {"oldest": 100}
+                }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let server_url = Url::from_str(&mock_server.uri()).unwrap();
+        let rpc = JsonRpc::new(server_url, &options(RetryPolicy::none())).unwrap();
+
+        let err = rpc.call("getEvents", json!(null)).await.unwrap_err();
+        assert_eq!(err.rpc_code(), Some(RpcErrorCode::StartLedgerOutOfRange));
+        assert!(!err.is_retryable());
+        assert_eq!(err.request_id(), Some(1));
+        assert!(err.to_string().ends_with("(request 1)"));
+        assert!(
+            matches!(err, Error::RPCError { code: -32600, data: Some(data), .. } if data == json!({"oldest": 100}))
+        );
+
+        // Only matched with the codes of invalid requests
+        let out_of_range =
+            "startLedger must be between the oldest ledger: 1 and the latest ledger: 2";
+        assert_eq!(
+            RpcErrorCode::new(-32602, out_of_range),
+            RpcErrorCode::StartLedgerOutOfRange
+        );
+        assert_eq!(
+            RpcErrorCode::new(-32603, out_of_range),
+            RpcErrorCode::Internal
+        );
+        assert_eq!(
+            RpcErrorCode::new(-32600, "startLedger between"),
+            RpcErrorCode::InvalidRequest
+        );
+        assert_eq!(RpcErrorCode::new(-32601, ""), RpcErrorCode::MethodNotFound);
+        assert_eq!(
+            RpcErrorCode::new(-32602, "bad"),
+            RpcErrorCode::InvalidParams
+        );
+        assert_eq!(RpcErrorCode::new(-32001, ""), RpcErrorCode::Server(-32001));
+        assert_eq!(RpcErrorCode::new(42, ""), RpcErrorCode::Other(42));
+        let internal = Error::RPCError {
+            code: -32603,
+            message: "internal".into(),
+            data: None,
+            request_id: None,
+        };
+        assert!(internal.is_retryable());
+        let mismatch = ResponseIdMismatch {
+            expected: 1,
+            found: Some(2),
+        };
+        assert!(!mismatch.is_retryable());
+    }
+
+    #[tokio::test]
+    async fn rate_limited() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(429).insert_header("Retry-After", "7"))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+
+        let server_url = Url::from_str(&mock_server.uri()).unwrap();
+        let rpc = JsonRpc::new(server_url, &options(RetryPolicy::none())).unwrap();
+
+        let response = rpc.post::<_, u32>("echo", json!(null)).await;
+        assert!(matches!(
+            response,
+            Err(RateLimited { retry_after }) if retry_after == Some(Duration::from_secs(7))
+        ));
+    }
+
+    #[test]
+    fn retry_after_date() {
+        // Sun, 06 Nov 1994 08:49:37 GMT
+        let now = Duration::from_secs(784111777);
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:49:47 GMT", now),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            parse_retry_after("Sun, 06 Nov 1994 08:49:00 GMT", now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(parse_retry_after(" 3 ", now), Some(Duration::from_secs(3)));
+        assert_eq!(parse_retry_after("soon", now), None);
+    }
+
+    #[tokio::test]
+    async fn rate_limit() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(EchoId::new(json!({"jsonrpc": "2.0", "id": 1, "result": 1})))
+            .expect(4)
+            .mount(&mock_server)
+            .await;
+
+        let server_url = Url::from_str(&mock_server.uri()).unwrap();
+        let opts = Options {
+            rate_limit: Some(RateLimit {
+                requests_per_second: 20.0,
+                burst: 2,
+            }),
+            ..options(RetryPolicy::none())
+        };
+        let rpc = JsonRpc::new(server_url, &opts).unwrap();
+
+        let start = std::time::Instant::now();
+        for _ in 0..4 {
+            rpc.post::<_, u32>("echo", json!(null)).await.unwrap();
+        }
+        // 2 requests in the burst, then one every 50ms
+        assert!(start.elapsed() >= Duration::from_millis(90));
+    }
+
+    #[tokio::test]
+    async fn max_in_flight() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(
+                EchoId::new(json!({"jsonrpc": "2.0", "id": 1, "result": 1}))
+                    .delay(Duration::from_millis(100)),
+            )
+            .expect(3)
+            .mount(&mock_server)
+            .await;
+
+        let server_url = Url::from_str(&mock_server.uri()).unwrap();
+        let opts = Options {
+            max_in_flight: Some(1),
+            ..options(RetryPolicy::none())
+        };
+        let rpc = JsonRpc::new(server_url, &opts).unwrap();
+
+        let start = std::time::Instant::now();
+        let (a, b, c) = tokio::join!(
+            rpc.post::<_, u32>("echo", json!(null)),
+            rpc.post::<_, u32>("echo", json!(null)),
+            rpc.post::<_, u32>("echo", json!(null))
+        );
+        assert!(a.is_ok() && b.is_ok() && c.is_ok());
+        assert!(start.elapsed() >= Duration::from_millis(300));
+    }
+
+    #[tokio::test]
+    async fn request_ids() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(matchers::body_partial_json(json!({"method": "echo"})))
+            .respond_with(EchoId::new(json!({"jsonrpc": "2.0", "id": 1, "result": 1})))
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(matchers::body_partial_json(json!({"method": "other"})))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"jsonrpc": "2.0", "id": 99, "result": 1})),
+            )
+            .mount(&mock_server)
+            .await;
+
+        let server_url = Url::from_str(&mock_server.uri()).unwrap();
+        let rpc = JsonRpc::new(server_url, &options(RetryPolicy::none())).unwrap();
+
+        rpc.post::<_, u32>("echo", json!(null)).await.unwrap();
+        rpc.post::<_, u32>("echo", json!(null)).await.unwrap();
+        let response = rpc.post::<_, u32>("other", json!(null)).await;
+        assert!(matches!(
+            response,
+            Err(ResponseIdMismatch {
+                expected: 3,
+                found: Some(99)
+            })
+        ));
+
+        let ids: Vec<u64> = mock_server
+            .received_requests()
+            .await
+            .unwrap()
+            .iter()
+            .map(|r| {
+                r.body_json::<serde_json::Value>().unwrap()["id"]
+                    .as_u64()
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(ids, vec![1, 2, 3]);
+    }
+
+    #[tokio::test]
+    async fn circuit_breaker() {
+        let mock_server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .respond_with(ResponseTemplate::new(503).set_body_string("unavailable"))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        let server_url = Url::from_str(&mock_server.uri()).unwrap();
+        let opts = Options {
+            circuit_breaker: Some(CircuitBreaker {
+                failure_threshold: 2,
+                reset_timeout: Duration::from_millis(50),
+            }),
+            ..options(RetryPolicy::none())
+        };
+        let rpc = JsonRpc::new(server_url, &opts).unwrap();
+
+        for _ in 0..2 {
+            let response = rpc.call("echo", json!(null)).await;
+            assert!(matches!(response, Err(JsonError { .. })));
+        }
+        // Fails fast without sending the request
+        let response = rpc.call("echo", json!(null)).await;
+        assert!(matches!(response, Err(CircuitOpen { .. })));
+        mock_server.verify().await;
+
+        mock_server.reset().await;
+        Mock::given(method("POST"))
+            .and(matchers::body_partial_json(json!({"method": "getHealth"})))
+            .respond_with(EchoId::new(json!({
+                "jsonrpc": "2.0",
+                "id": 1,
+                "result": {
+                    "status": "healthy",
+                    "latestLedger": 100,
+                    "oldestLedger": 1,
+                    "ledgerRetentionWindow": 100
+                }
+            })))
+            .expect(1)
+            .mount(&mock_server)
+            .await;
+        Mock::given(method("POST"))
+            .and(matchers::body_partial_json(json!({"method": "echo"})))
+            .respond_with(EchoId::new(json!({"jsonrpc": "2.0", "id": 1, "result": 1})))
+            .expect(2)
+            .mount(&mock_server)
+            .await;
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        // Probed with getHealth, then closed
+        assert_eq!(rpc.call("echo", json!(null)).await.unwrap(), json!(1));
+        assert_eq!(rpc.call("echo", json!(null)).await.unwrap(), json!(1));
+    }
+
+    #[test]
+    fn retry_delay() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(3),
+            jitter: false,
+            ..Default::default()
+        };
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(2), Duration::from_secs(2));
+        assert_eq!(policy.delay(3), Duration::from_secs(3));
+        assert_eq!(policy.delay(30), Duration::from_secs(3));
+
+        let policy = RetryPolicy {
+            jitter: true,
+            ..policy
+        };
+        let delay = policy.delay(2);
+        assert!(delay >= Duration::from_secs(1) && delay <= Duration::from_secs(2));
+    }
 }
@@ -94,24 +94,81 @@
 //!    println!("{:?}", events);
 //!}
 //! ```
+//!
+//!# Tracing and metrics
+//!
+//! Each RPC call made by a [Server] is wrapped in an `rpc` [tracing] span with the fields
+//! `method`, `endpoint`, `request_id`, `duration_ms`, `outcome` (`success`, `rpc_error` or
+//! `error`) and `rpc_code`. Failed calls are also logged at the `WARN` level.
+//!
+//! With the `metrics` feature, the following [metrics](https://docs.rs/metrics) are recorded:
+//! - `soroban_client_requests_total` counter, labeled by `method` and `outcome`
+//! - `soroban_client_request_duration_seconds` histogram, labeled by `method`
+//! - `soroban_client_rpc_errors_total` counter, labeled by `method` and `code`
+//!
+//!# WebAssembly
+//!
+//! The crate builds for `wasm32-unknown-unknown` with the `wasm` feature. Requests are sent with
+//! the browser `fetch` API and the timers use the browser `setTimeout`, so the futures must be
+//! run by `wasm-bindgen-futures` instead of tokio. Custom [RpcTransport](transport::RpcTransport)
+//! implementations must use `#[async_trait(?Send)]` on this target.
+//!
 //! [Stellar RPC]: https://developers.stellar.org/docs/data/rpc
 
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+compile_error!("the `wasm` feature is required to build for wasm32");
+
+// The code generated by the derive macros refers to `::soroban_client`
+extern crate self as soroban_client;
+
 /// Current version of this crate
 pub static VERSION: &str = env!("CARGO_PKG_VERSION");
 pub use crate::server::*;
+/// Batch module, to send several RPC calls in one request
+pub mod batch;
+/// Blocking module, a synchronous [Server] with an internal runtime
+#[cfg(feature = "blocking")]
+pub mod blocking;
+/// Cassette module, to record and replay the RPC traffic
+pub mod cassette;
+/// Client module, the [RpcClient](client::RpcClient) trait implemented by [Server]
+pub mod client;
 /// Error module
 pub mod error;
+/// Events module, to stream the events of the RPC
+pub mod events;
+/// Failover module, to use several RPC endpoints
+pub mod failover;
+/// Failure module, to explain why a transaction or a contract call failed
+pub mod failure;
+/// Ingest module, to deliver the events to a backend with checkpoints
+pub mod ingest;
+/// Middleware module, to hook into every request sent to the RPC
+pub mod middleware;
 /// Soroban bindings
 pub mod soroban_rpc;
+/// Testing module, with a fake RPC to test without network
+#[cfg(feature = "testing")]
+pub mod testing;
+/// Token module, to decode the events of the SEP-41 tokens
+pub mod token;
 /// Transaction module
 pub mod transaction;
+/// Transport module, to customize how the RPC is reached
+pub mod transport;
+/// Re-export of the HTTP client, see [Options::http_client]
+pub use reqwest;
 pub use stellar_baselib::*;
 
 // for now, not public
 //mod contract_spec;
+mod breaker;
 mod friendbot;
 mod jsonrpc;
+mod limiter;
 mod server;
+mod telemetry;
+mod time;
 
 #[cfg(test)]
 mod tests;
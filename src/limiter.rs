@@ -0,0 +1,77 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+use tokio::sync::{Semaphore, SemaphorePermit};
+
+use crate::server::RateLimit;
+use crate::time::{sleep, Instant};
+
+/// Token bucket refilled at `RateLimit::requests_per_second`, holding at most `RateLimit::burst`
+#[derive(Debug)]
+pub struct TokenBucket {
+    rate: f64,
+    capacity: f64,
+    state: Mutex<(f64, Instant)>,
+}
+
+impl TokenBucket {
+    pub fn new(limit: &RateLimit) -> Self {
+        let capacity = f64::from(limit.burst.max(1));
+        TokenBucket {
+            rate: limit.requests_per_second,
+            capacity,
+            state: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    /// Wait until a token is available and take it
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let (tokens, last) = *state;
+                let now = Instant::now();
+                let tokens = (tokens + now.duration_since(last).as_secs_f64() * self.rate)
+                    .min(self.capacity);
+                if tokens >= 1.0 {
+                    *state = (tokens - 1.0, now);
+                    return;
+                }
+                *state = (tokens, now);
+                Duration::from_secs_f64((1.0 - tokens) / self.rate)
+            };
+            sleep(wait).await;
+        }
+    }
+}
+
+/// Rate and concurrency limits applied before sending each HTTP request
+#[derive(Debug, Default)]
+pub struct Limiter {
+    bucket: Option<TokenBucket>,
+    in_flight: Option<Semaphore>,
+}
+
+impl Limiter {
+    pub fn new(rate_limit: Option<&RateLimit>, max_in_flight: Option<usize>) -> Self {
+        Limiter {
+            bucket: rate_limit
+                .filter(|l| l.requests_per_second > 0.0)
+                .map(TokenBucket::new),
+            in_flight: max_in_flight.map(|n| Semaphore::new(n.max(1))),
+        }
+    }
+
+    /// Wait for the limits, the returned permit must be kept until the response is received
+    pub async fn acquire(&self) -> Option<SemaphorePermit<'_>> {
+        let permit = match &self.in_flight {
+            // The semaphore is never closed
+            Some(semaphore) => semaphore.acquire().await.ok(),
+            None => None,
+        };
+        if let Some(bucket) = &self.bucket {
+            bucket.acquire().await;
+        }
+        permit
+    }
+}
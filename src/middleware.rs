@@ -0,0 +1,120 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+pub use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+
+use crate::error::Error;
+use crate::transport::async_trait;
+
+/// HTTP request about to be sent by [JsonRpc](crate::transport::JsonRpc)
+#[derive(Debug)]
+pub struct OutgoingRequest {
+    /// JSON-RPC method, `batch` for a batch request
+    pub method: String,
+    /// Headers added to the request, on top of [Options::headers](crate::Options::headers)
+    pub headers: HeaderMap,
+    /// JSON-RPC body of the request
+    pub body: Vec<u8>,
+}
+
+/// HTTP response received by [JsonRpc](crate::transport::JsonRpc)
+#[derive(Debug)]
+pub struct ReceivedResponse<'a> {
+    /// JSON-RPC method, `batch` for a batch request
+    pub method: &'a str,
+    /// Attempt number, starting at 1, see [RetryPolicy](crate::RetryPolicy)
+    pub attempt: u32,
+    /// HTTP status of the response
+    pub status: StatusCode,
+    /// Time between sending the request and receiving the whole response
+    pub elapsed: Duration,
+    /// Raw body of the response
+    pub text: &'a str,
+}
+
+/// Hook called around every HTTP request sent to the RPC
+///
+/// Middlewares are registered with [Options::middleware](crate::Options::middleware) and run
+/// for each attempt of every [Server](crate::Server) method, in the order they were added.
+///
+/// # Example
+/// ```rust
+/// # use soroban_client::*;
+/// # use soroban_client::error::Error;
+/// use soroban_client::middleware::{Middleware, OutgoingRequest, ReceivedResponse};
+///
+/// struct Auth;
+///
+/// #[soroban_client::transport::async_trait]
+/// impl Middleware for Auth {
+///     async fn before_send(&self, request: &mut OutgoingRequest) -> Result<(), Error> {
+///         request.headers.insert("Authorization", "Bearer token".parse().unwrap());
+///         Ok(())
+///     }
+///
+///     async fn after_receive(&self, response: &ReceivedResponse<'_>) {
+///         println!("{} took {:?}", response.method, response.elapsed);
+///     }
+/// }
+///
+/// # fn run() -> Result<(), Error> {
+/// let mut options = Options::default();
+/// options.middleware.push(Auth);
+/// let server = Server::new("https://rpc.server", options)?;
+/// # Ok(()) }
+/// ```
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    /// Called before the request is sent, an error aborts the request
+    async fn before_send(&self, _request: &mut OutgoingRequest) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Called once the response is received, before it is parsed
+    async fn after_receive(&self, _response: &ReceivedResponse<'_>) {}
+}
+
+/// Ordered list of [Middleware]
+#[derive(Clone, Default)]
+pub struct MiddlewareChain {
+    middlewares: Vec<Arc<dyn Middleware>>,
+}
+
+impl std::fmt::Debug for MiddlewareChain {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MiddlewareChain")
+            .field("len", &self.middlewares.len())
+            .finish()
+    }
+}
+
+impl MiddlewareChain {
+    /// Add `middleware` at the end of the chain
+    pub fn push(&mut self, middleware: impl Middleware + 'static) {
+        self.middlewares.push(Arc::new(middleware));
+    }
+
+    /// Number of middlewares in the chain
+    pub fn len(&self) -> usize {
+        self.middlewares.len()
+    }
+
+    /// True if the chain is empty
+    pub fn is_empty(&self) -> bool {
+        self.middlewares.is_empty()
+    }
+
+    pub(crate) async fn before_send(&self, request: &mut OutgoingRequest) -> Result<(), Error> {
+        for middleware in &self.middlewares {
+            middleware.before_send(request).await?;
+        }
+        Ok(())
+    }
+
+    pub(crate) async fn after_receive(&self, response: &ReceivedResponse<'_>) {
+        for middleware in &self.middlewares {
+            middleware.after_receive(response).await;
+        }
+    }
+}
@@ -1,9 +1,18 @@
-use crate::jsonrpc::{JsonRpc, Response};
+use crate::batch::Batch;
+use crate::failover::{Failover, FailoverOptions};
+use crate::jsonrpc::JsonRpc;
+use crate::middleware::MiddlewareChain;
+use crate::telemetry;
+use crate::time::{sleep, Instant};
 use crate::transaction::assemble_transaction;
+use crate::transport::RpcTransport;
 use crate::{error, soroban_rpc::*};
 use crate::{error::*, friendbot};
 use futures::TryFutureExt;
+use serde::de::DeserializeOwned;
 use serde_json::json;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
 use std::option::Option;
 use std::time::Duration;
 use std::{collections::HashMap, str::FromStr};
@@ -16,7 +25,7 @@ use stellar_baselib::xdr::{
     ContractDataDurability, LedgerEntryData, LedgerKey, LedgerKeyAccount, LedgerKeyContractData,
     Limits, ScVal, WriteXdr,
 };
-use tokio::time::{sleep, Instant};
+use tracing::Instrument;
 
 /// The default transaction submission timeout for RPC requests, in milliseconds.
 pub const SUBMIT_TRANSACTION_TIMEOUT: u32 = 60 * 1000;
@@ -55,6 +64,7 @@ impl Durability {
 /// which appear after the cursor will be included.
 ///
 /// [TOID]: https://github.com/stellar/stellar-protocol/blob/master/ecosystem/sep-0035.md#specification
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Pagination {
     /// Fetch events starting at this ledger sequence
     From(u32),
@@ -63,9 +73,20 @@ pub enum Pagination {
     /// Fetch events after this cursor
     Cursor(String),
 }
+/// Maximum number of [EventFilter] in a [Server::get_events] request
+pub const MAX_EVENT_FILTERS: usize = 5;
+
+/// Maximum number of contract IDs, and of topic filters, in an [EventFilter]
+pub const MAX_EVENT_FILTER_ITEMS: usize = 5;
+
+/// Maximum number of concurrent requests of [Server::get_events] when the filters are fanned
+/// out, see [Options::fan_out_events]
+pub const MAX_FAN_OUT_REQUESTS: usize = 4;
+
 /// List of filters for the returned events. Events matching any of the filters are included.
 /// To match a filter, an event must match both a contractId and a topic. Maximum 5 filters are
-/// allowed per request.
+/// allowed per request, unless [Options::fan_out_events] is set.
+#[derive(Debug, Clone)]
 pub struct EventFilter {
     event_type: EventType,
     contract_ids: Vec<String>,
@@ -141,6 +162,28 @@ impl EventFilter {
             })
             .collect()
     }
+
+    /// Split the filter into filters within [MAX_EVENT_FILTER_ITEMS], matching the same events
+    pub(crate) fn split(&self) -> Vec<EventFilter> {
+        let contracts: Vec<&[String]> = match self.contract_ids.len() {
+            0 => vec![&[]],
+            _ => self.contract_ids.chunks(MAX_EVENT_FILTER_ITEMS).collect(),
+        };
+        let topics: Vec<&[Vec<Topic>]> = match self.topics.len() {
+            0 => vec![&[]],
+            _ => self.topics.chunks(MAX_EVENT_FILTER_ITEMS).collect(),
+        };
+        contracts
+            .iter()
+            .flat_map(|contract_ids| {
+                topics.iter().map(|topics| EventFilter {
+                    event_type: self.event_type.clone(),
+                    contract_ids: contract_ids.to_vec(),
+                    topics: topics.to_vec(),
+                })
+            })
+            .collect()
+    }
 }
 
 /// Contains configuration for how resources will be calculated when simulating transactions.
@@ -186,6 +229,105 @@ impl From<AuthMode> for &str {
     }
 }
 
+/// Retry policy applied to every RPC request
+///
+/// A request is retried when the connection fails or times out, when the HTTP status is listed
+/// in `retry_statuses` or when the JSON-RPC error code is listed in `retry_rpc_codes`. The delay
+/// between two attempts doubles from `base_delay` up to `max_delay`.
+///
+/// The request body is serialized once and sent as-is on every attempt. Retrying
+/// [Server::send_transaction] is safe: the network identifies a transaction by its hash, so a
+/// resubmitted envelope is never applied twice.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    /// Maximum number of attempts, including the first one (default: 3). `1` disables retries.
+    pub max_attempts: u32,
+    /// Delay before the first retry (default: 250ms)
+    pub base_delay: Duration,
+    /// Maximum delay between two attempts (default: 5s)
+    pub max_delay: Duration,
+    /// Randomize each delay between half and all of its value (default: true)
+    pub jitter: bool,
+    /// HTTP statuses considered as transient (default: 429, 502, 503, 504)
+    pub retry_statuses: Vec<u16>,
+    /// JSON-RPC error codes considered as transient (default: none)
+    pub retry_rpc_codes: Vec<i32>,
+}
+
+impl RetryPolicy {
+    /// A policy sending each request exactly once
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Default::default()
+        }
+    }
+
+    /// Delay to wait after the failed `attempt` (starting at 1)
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let exp = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)));
+        let delay = std::cmp::min(exp, self.max_delay);
+        if self.jitter {
+            // Hashing with a randomly seeded hasher is enough randomness for a jitter
+            let random = RandomState::new().build_hasher().finish();
+            let factor = 0.5 + (random % 1000) as f64 / 2000.0;
+            delay.mul_f64(factor)
+        } else {
+            delay
+        }
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(250),
+            max_delay: Duration::from_secs(5),
+            jitter: true,
+            retry_statuses: vec![429, 502, 503, 504],
+            retry_rpc_codes: Vec::new(),
+        }
+    }
+}
+
+/// Client-side rate limit, see [Options::rate_limit]
+///
+/// Requests are limited with a token bucket: up to `burst` requests can be sent at once, then
+/// `requests_per_second` on average.
+#[derive(Debug, Clone)]
+pub struct RateLimit {
+    /// Sustained number of requests per second
+    pub requests_per_second: f64,
+    /// Maximum number of requests sent in a burst
+    pub burst: u32,
+}
+
+/// Circuit breaker of an RPC endpoint, see [Options::circuit_breaker]
+///
+/// After `failure_threshold` consecutive failures (network errors, invalid responses or rate
+/// limits), the circuit opens: requests fail immediately with [Error::CircuitOpen] for
+/// `reset_timeout`. Then a single [get_health](Server::get_health) probe is sent, and the circuit
+/// closes again if the endpoint is healthy.
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    /// Number of consecutive failures opening the circuit (default: 5)
+    pub failure_threshold: u32,
+    /// Time the circuit stays open before probing the endpoint (default: 30s)
+    pub reset_timeout: Duration,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            reset_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
 /// Additionnal options
 #[derive(Debug)]
 pub struct Options {
@@ -197,6 +339,30 @@ pub struct Options {
     pub headers: HashMap<String, String>,
     /// Optional friendbot URL
     pub friendbot_url: Option<String>,
+    /// Retry policy for transient failures, see [RetryPolicy]
+    pub retry: RetryPolicy,
+    /// Optional client-side rate limit, applied to every request including retries
+    pub rate_limit: Option<RateLimit>,
+    /// Optional maximum number of requests waiting for a response at the same time
+    pub max_in_flight: Option<usize>,
+    /// Optional circuit breaker, failing fast while the RPC is down
+    pub circuit_breaker: Option<CircuitBreaker>,
+    /// Hooks called around every HTTP request, see [Middleware](crate::middleware::Middleware)
+    pub middleware: MiddlewareChain,
+    /// Health checks of the endpoints, used by [Server::with_endpoints]
+    pub failover: FailoverOptions,
+    /// Optional preconfigured HTTP client, to set proxies, root certificates, client
+    /// certificates, connection pool or HTTP/2 settings
+    ///
+    /// It is used for the RPC requests and [Server::request_airdrop]. The [Options::timeout] and
+    /// [Options::headers] are still applied to each RPC request.
+    pub http_client: Option<reqwest::Client>,
+    /// If true, [Server::get_events] splits the filters over [MAX_EVENT_FILTERS] and
+    /// [MAX_EVENT_FILTER_ITEMS] into requests, at most [MAX_FAN_OUT_REQUESTS] at a time, and
+    /// merges their events. Otherwise these filters are rejected with [Error::EventFilterLimit]. A
+    /// [Batch::get_events](crate::batch::Batch::get_events) call is a single request, its
+    /// filters are always rejected (default: false)
+    pub fan_out_events: bool,
 }
 
 impl Default for Options {
@@ -206,15 +372,32 @@ impl Default for Options {
             timeout: 10,
             headers: Default::default(),
             friendbot_url: None,
+            retry: RetryPolicy::default(),
+            rate_limit: None,
+            max_in_flight: None,
+            circuit_breaker: None,
+            middleware: MiddlewareChain::default(),
+            failover: FailoverOptions::default(),
+            http_client: None,
+            fan_out_events: false,
         }
     }
 }
 
 /// The main struct to use to interact with the stellar RPC
-#[derive(Debug)]
 pub struct Server {
-    client: JsonRpc,
+    client: Box<dyn RpcTransport>,
     friendbot_url: Option<String>,
+    http_client: Option<reqwest::Client>,
+    fan_out_events: bool,
+}
+
+impl std::fmt::Debug for Server {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Server")
+            .field("friendbot_url", &self.friendbot_url)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Server {
@@ -225,28 +408,73 @@ impl Server {
     /// let rpc = Server::new("https://soroban-testnet.stellar.org", Options::default());
     /// ```
     pub fn new(server_url: &str, opts: Options) -> Result<Self, Error> {
-        let server_url = reqwest::Url::from_str(server_url)
-            .map_err(|_e| Error::InvalidRpc(InvalidRpcUrl::InvalidUri))?;
-        let allow_http = opts.allow_http;
-        match server_url.scheme() {
-            "https" => {
-                // good
-            }
-            "http" if allow_http => {
-                // good
-            }
-            "http" if !allow_http => {
-                return Err(Error::InvalidRpc(InvalidRpcUrl::UnsecureHttpNotAllowed));
-            }
-            _ => {
-                return Err(Error::InvalidRpc(InvalidRpcUrl::NotHttpScheme));
-            }
-        };
+        let client = JsonRpc::with_options(server_url, &opts)?;
+        Ok(Self::with_transport(client, opts))
+    }
+
+    /// # Instantiate a new [Server] using several RPC endpoints
+    ///
+    /// Requests are routed to the healthiest endpoint and sent to the next one on network errors.
+    /// All the endpoints must serve the same network. See [Failover] for details.
+    ///
+    /// ```rust
+    /// use soroban_client::*;
+    /// let rpc = Server::with_endpoints(
+    ///     &["https://rpc-1.example.com", "https://rpc-2.example.com"],
+    ///     Options::default(),
+    /// );
+    /// ```
+    pub fn with_endpoints(server_urls: &[&str], opts: Options) -> Result<Self, Error> {
+        let client = Failover::new(server_urls, &opts)?;
+        Ok(Self::with_transport(client, opts))
+    }
 
-        Ok(Server {
-            client: JsonRpc::new(server_url, opts.timeout, opts.headers),
+    /// # Instantiate a new [Server] using a custom [RpcTransport]
+    ///
+    /// Every RPC method goes through `transport`. Only [Options::friendbot_url],
+    /// [Options::http_client] and [Options::fan_out_events] are used from `opts`, the other
+    /// options configure the default [JsonRpc] transport.
+    ///
+    /// ```rust
+    /// use soroban_client::*;
+    /// use soroban_client::transport::JsonRpc;
+    /// # fn build() -> Result<(), soroban_client::error::Error> {
+    /// let transport = JsonRpc::with_options("https://soroban-testnet.stellar.org", &Options::default())?;
+    /// let rpc = Server::with_transport(transport, Options::default());
+    /// # Ok(()) }
+    /// ```
+    pub fn with_transport(transport: impl RpcTransport + 'static, opts: Options) -> Self {
+        Server {
+            client: Box::new(transport),
             friendbot_url: opts.friendbot_url,
-        })
+            http_client: opts.http_client,
+            fan_out_events: opts.fan_out_events,
+        }
+    }
+
+    /// # Start a [Batch] of RPC calls
+    ///
+    /// The queued calls are sent in a single HTTP request, see [Batch].
+    pub fn batch(&self) -> Batch<'_> {
+        Batch::new(self.client.as_ref())
+    }
+
+    // Call the RPC `method` in its own span, see the "Tracing and metrics" section of the crate
+    async fn request<T: DeserializeOwned>(
+        &self,
+        method: &str,
+        params: serde_json::Value,
+    ) -> Result<T, Error> {
+        let span = telemetry::rpc_span(method);
+        let start = Instant::now();
+        let result = self
+            .client
+            .call(method, params)
+            .instrument(span.clone())
+            .await
+            .and_then(handle_response);
+        telemetry::record(&span, method, start.elapsed(), &result);
+        result
     }
 
     // RPC method implementations -------------------------------
@@ -267,6 +495,9 @@ impl Server {
     ///
     /// By default stellar-rpc retains the most recent 24 hours of events.
     ///
+    /// Use [Server::events_stream] to follow the pages of events, and
+    /// [Ingester](crate::ingest::Ingester) to ingest them with a checkpoint.
+    ///
     /// # Example
     /// ```rust
     /// // Fetch 12 events from ledger 67000 for contract "CAA..."
@@ -294,37 +525,14 @@ impl Server {
         filters: Vec<EventFilter>,
         limit: impl Into<Option<u32>>,
     ) -> Result<GetEventsResponse, Error> {
-        let (start_ledger, end_ledger, cursor) = match ledger {
-            Pagination::From(s) => (Some(s), None, None),
-            Pagination::FromTo(s, e) => (Some(s), Some(e), None),
-            Pagination::Cursor(c) => (None, None, Some(c)),
-        };
-        let filters = filters
-            .into_iter()
-            .map(|v| {
-                //
-                json!({
-                    "type": v.event_type(),
-                    "contractIds": v.contracts(),
-                    "topics": v.topics(),
-                })
-            })
-            .collect::<Vec<serde_json::Value>>();
-
-        let params = json!(
-        {
-            "startLedger": start_ledger,
-            "endLedger": end_ledger,
-            "filters": filters,
-            "pagination": {
-                "cursor": cursor,
-                "limit": limit.into()
-            }
+        if self.fan_out_events && validate_event_filters(&filters).is_err() {
+            return self
+                .get_events_fanned_out(ledger, filters, limit.into())
+                .await;
         }
-        );
-
-        let response = self.client.post("getEvents", params).await?;
-        handle_response(response)
+        validate_event_filters(&filters)?;
+        let params = events_params(ledger, filters, limit.into());
+        self.request("getEvents", params).await
     }
 
     /// # Call to RPC method [getFeeStats]
@@ -338,11 +546,7 @@ impl Server {
     /// [getFeeStats]: https://developers.stellar.org/docs/data/rpc/api-reference/methods/getFeeStats
     ///
     pub async fn get_fee_stats(&self) -> Result<GetFeeStatsResponse, Error> {
-        let response = self
-            .client
-            .post("getFeeStats", serde_json::Value::Null)
-            .await?;
-        handle_response(response)
+        self.request("getFeeStats", serde_json::Value::Null).await
     }
 
     /// # Call to RPC method [getHealth]
@@ -352,11 +556,7 @@ impl Server {
     /// [getHealth]: https://developers.stellar.org/docs/data/rpc/api-reference/methods/getHealth
     ///
     pub async fn get_health(&self) -> Result<GetHealthResponse, Error> {
-        let response = self
-            .client
-            .post("getHealth", serde_json::Value::Null)
-            .await?;
-        handle_response(response)
+        self.request("getHealth", serde_json::Value::Null).await
     }
 
     /// # Call to RPC method [getLatestLedger]
@@ -367,11 +567,8 @@ impl Server {
     /// [getLatestLedger]: https://developers.stellar.org/docs/data/rpc/api-reference/methods/getLatestLedger
     ///
     pub async fn get_latest_ledger(&self) -> Result<GetLatestLedgerResponse, Error> {
-        let response = self
-            .client
-            .post("getLatestLedger", serde_json::Value::Null)
-            .await?;
-        handle_response(response)
+        self.request("getLatestLedger", serde_json::Value::Null)
+            .await
     }
 
     /// # Call to RPC method [getLedgerEntries]
@@ -393,21 +590,8 @@ impl Server {
         &self,
         keys: Vec<LedgerKey>,
     ) -> Result<GetLedgerEntriesResponse, Error> {
-        let keys: Result<Vec<String>, Error> = keys
-            .into_iter()
-            .map(|k| k.to_xdr_base64(Limits::none()).map_err(|_| Error::XdrError))
-            .collect();
-
-        match keys {
-            Ok(keys) => {
-                let params = json!({"keys": keys});
-                let response: Response<GetLedgerEntriesResponse> =
-                    self.client.post("getLedgerEntries", params).await?;
-
-                handle_response(response)
-            }
-            Err(err) => Err(err),
-        }
+        let params = ledger_entries_params(keys)?;
+        self.request("getLedgerEntries", params).await
     }
 
     /// # Call to RPC method [getLedgers]
@@ -422,23 +606,9 @@ impl Server {
         ledger: Pagination,
         limit: impl Into<Option<u32>>,
     ) -> Result<GetLedgersResponse, Error> {
-        let (start_ledger, cursor) = match ledger {
-            Pagination::From(s) => (Some(s), None),
-            Pagination::FromTo(s, _) => (Some(s), None),
-            Pagination::Cursor(c) => (None, Some(c)),
-        };
-        let params = json!(
-        {
-            "startLedger": start_ledger,
-            "pagination": {
-                "cursor": cursor,
-                "limit": limit.into()
-            }
-        }
-        );
+        let params = paginated_params(ledger, limit.into());
 
-        let response = self.client.post("getLedgers", params).await?;
-        handle_response(response)
+        self.request("getLedgers", params).await
     }
 
     /// # Call to RPC method [getNetwork]
@@ -448,11 +618,7 @@ impl Server {
     ///
     /// [getNetwork]: https://developers.stellar.org/docs/data/rpc/api-reference/methods/getNetwork
     pub async fn get_network(&self) -> Result<GetNetworkResponse, Error> {
-        let response = self
-            .client
-            .post("getNetwork", serde_json::Value::Null)
-            .await?;
-        handle_response(response)
+        self.request("getNetwork", serde_json::Value::Null).await
     }
 
     /// # Call to RPC method [getTransaction]
@@ -477,8 +643,7 @@ impl Server {
                 "hash": hash
         });
 
-        let response = self.client.post("getTransaction", params).await?;
-        handle_response(response)
+        self.request("getTransaction", params).await
     }
 
     /// # Call to RPC method [getTransactions]
@@ -495,23 +660,9 @@ impl Server {
         ledger: Pagination,
         limit: impl Into<Option<u32>>,
     ) -> Result<GetTransactionsResponse, Error> {
-        let (start_ledger, cursor) = match ledger {
-            Pagination::From(s) => (Some(s), None),
-            Pagination::FromTo(s, _) => (Some(s), None),
-            Pagination::Cursor(c) => (None, Some(c)),
-        };
-        let params = json!(
-        {
-            "startLedger": start_ledger,
-            "pagination": {
-                "cursor": cursor,
-                "limit": limit.into()
-            }
-        }
-        );
+        let params = paginated_params(ledger, limit.into());
 
-        let response = self.client.post("getTransactions", params).await?;
-        handle_response(response)
+        self.request("getTransactions", params).await
     }
 
     /// # Call to RPC method [getVersionInfo]
@@ -522,11 +673,8 @@ impl Server {
     ///
     /// [getVersionInfo]: https://developers.stellar.org/docs/data/rpc/api-reference/methods/getVersionInfo
     pub async fn get_version_info(&self) -> Result<GetVersionInfoResponse, Error> {
-        let response = self
-            .client
-            .post("getVersionInfo", serde_json::Value::Null)
-            .await?;
-        handle_response(response)
+        self.request("getVersionInfo", serde_json::Value::Null)
+            .await
     }
 
     /// # Call to RPC method [sendTransaction]
@@ -540,24 +688,18 @@ impl Server {
     ///
     /// This supports all transactions, not only smart contract-related transactions.
     ///
+    /// When the request is retried (see [RetryPolicy]), the status can be
+    /// [SendTransactionStatus::Duplicate] if a previous attempt reached the network: the
+    /// transaction has been enqueued once and can be followed with its hash.
+    ///
     /// [sendTransaction]: https://developers.stellar.org/docs/data/rpc/api-reference/methods/sendTransaction
     ///
     pub async fn send_transaction(
         &self,
         transaction: Transaction,
     ) -> Result<SendTransactionResponse, Error> {
-        let transaction_xdr = transaction
-            .to_envelope()
-            .map_err(|_| Error::TransactionError)?
-            .to_xdr_base64(Limits::none())
-            .map_err(|_| Error::XdrError)?;
-
-        let params = json!({
-                "transaction": transaction_xdr
-            }
-        );
-        let response = self.client.post("sendTransaction", params).await?;
-        handle_response(response)
+        let params = send_transaction_params(&transaction)?;
+        self.request("sendTransaction", params).await
     }
 
     /// # Call to RPC method [simulateTransaction]
@@ -573,29 +715,9 @@ impl Server {
         transaction: &Transaction,
         options: Option<SimulationOptions>,
     ) -> Result<SimulateTransactionResponse, Error> {
-        let transaction_xdr = transaction
-            .to_envelope()
-            .map_err(|_| Error::TransactionError)?
-            .to_xdr_base64(Limits::none())
-            .map_err(|_| Error::XdrError)?;
-
-        // Add resource config if provided
-        let params = if let Some(resources) = options {
-            json!({
-                "transaction": transaction_xdr,
-                "resourceConfig": {
-                    "instructionLeeway": resources.cpu_instructions
-                },
-                "authMode": resources.auth_mode.map(|a| {let mode: &str = a.into(); mode}),
-            })
-        } else {
-            json!({
-                "transaction": transaction_xdr
-            })
-        };
+        let params = simulate_transaction_params(transaction, options)?;
 
-        let response = self.client.post("simulateTransaction", params).await?;
-        handle_response(response)
+        self.request("simulateTransaction", params).await
     }
 
     // Non-RPC method implementations -------------------------------
@@ -604,22 +726,34 @@ impl Server {
     ///
     /// It uses [Server::get_ledger_entries] to fetch the [LedgerKey::Account]
     ///
+    #[tracing::instrument(skip(self))]
     pub async fn get_account(&self, address: &str) -> Result<Account, Error> {
         let account_id = stellar_baselib::keypair::Keypair::from_public_key(address)
-            .map_err(|_| Error::AccountNotFound)?
+            .map_err(|e| Error::InvalidAddress {
+                address: address.to_string(),
+                source: Cause(e.to_string()),
+            })?
             .xdr_account_id();
         let ledger_key = LedgerKey::Account(LedgerKeyAccount { account_id });
 
         let resp = self.get_ledger_entries(vec![ledger_key]).await?;
         let entries = resp.entries.unwrap_or_default();
+        let not_found = || Error::AccountNotFound {
+            address: address.to_string(),
+        };
         if entries.is_empty() {
-            return Err(Error::AccountNotFound);
+            return Err(not_found());
         }
 
-        if let LedgerEntryData::Account(account_entry) = entries[0].to_data() {
-            Ok(Account::new(address, &account_entry.seq_num.0.to_string()).unwrap())
+        if let LedgerEntryData::Account(account_entry) = entries[0].try_to_data()? {
+            Account::new(address, &account_entry.seq_num.0.to_string()).map_err(|e| {
+                Error::InvalidAddress {
+                    address: address.to_string(),
+                    source: Cause(e.to_string()),
+                }
+            })
         } else {
-            Err(Error::AccountNotFound)
+            Err(not_found())
         }
     }
 
@@ -628,16 +762,21 @@ impl Server {
     /// This can be used to inspect the contract state without using [Server::simulate_transaction]
     /// or to fetch data not available otherwise.
     ///
+    #[tracing::instrument(skip(self, key, durability))]
     pub async fn get_contract_data(
         &self,
         contract: &str,
         key: ScVal,
         durability: Durability,
     ) -> Result<LedgerEntryResult, Error> {
+        let invalid_address = |e: &str| Error::InvalidAddress {
+            address: contract.to_string(),
+            source: Cause(e.to_string()),
+        };
         let sc_address = Address::new(contract)
-            .map_err(|_| Error::ContractDataNotFound)?
+            .map_err(invalid_address)?
             .to_sc_address()
-            .map_err(|_| Error::ContractDataNotFound)?;
+            .map_err(invalid_address)?;
 
         let contract_key = LedgerKey::ContractData(LedgerKeyContractData {
             key: key.clone(),
@@ -649,15 +788,13 @@ impl Server {
 
         let response = self.get_ledger_entries(val).await?;
 
-        if let Some(entries) = response.entries {
-            if let Some(entry) = entries.first() {
-                Ok(entry.clone())
-            } else {
-                Err(Error::ContractDataNotFound)
-            }
-        } else {
-            Err(Error::ContractDataNotFound)
-        }
+        response
+            .entries
+            .and_then(|entries| entries.into_iter().next())
+            .ok_or(Error::ContractDataNotFound {
+                contract: contract.to_string(),
+                key,
+            })
     }
 
     /// # Prepare a transaction to be submited to the network.
@@ -672,6 +809,7 @@ impl Server {
     /// This error should be used to build a
     /// [stellar_baselib::xdr::OperationBody::RestoreFootprint]
     ///
+    #[tracing::instrument(skip_all)]
     pub async fn prepare_transaction(
         &self,
         transaction: &Transaction,
@@ -686,7 +824,10 @@ impl Server {
     /// The friendbot URL is retrieved first from the [Options::friendbot_url] if provided
     /// or from the [Server::get_network] method. There is no friendbot faucet on mainnet.
     ///
+    /// The request is sent with the [Options::http_client] if provided.
+    ///
     /// [friendbot]: https://developers.stellar.org/docs/learn/fundamentals/networks#friendbot
+    #[tracing::instrument(skip(self))]
     pub async fn request_airdrop(&self, account_id: &str) -> Result<Account, Error> {
         let friendbot_url = if let Some(url) = self.friendbot_url.clone() {
             url
@@ -699,24 +840,26 @@ impl Server {
             }
         };
 
-        let client = reqwest::ClientBuilder::new()
-            .build()
-            .map_err(Error::NetworkError)?;
+        let client = match &self.http_client {
+            Some(client) => client.clone(),
+            None => reqwest::ClientBuilder::new().build().map_err(Error::from)?,
+        };
 
         let response = client
             .get(friendbot_url + "?addr=" + account_id)
             .send()
-            .map_err(Error::NetworkError)
+            .map_err(Error::from)
             .await?;
 
-        let data: friendbot::FriendbotResponse =
-            response.json().map_err(Error::NetworkError).await?;
+        let data: friendbot::FriendbotResponse = response.json().map_err(Error::from).await?;
 
         if let Some(success) = data.successful {
             if success {
                 self.get_account(account_id).await
             } else {
-                Err(Error::AccountNotFound)
+                Err(Error::AccountNotFound {
+                    address: account_id.to_string(),
+                })
             }
         } else {
             // If we don't get a success, it can be already funded
@@ -732,6 +875,7 @@ impl Server {
     ///
     /// If an error occurs you can get the last result of [Server::get_transaction] with the
     /// [Error].
+    #[tracing::instrument(skip(self))]
     pub async fn wait_transaction(
         &self,
         hash: &str,
@@ -759,23 +903,165 @@ impl Server {
             }
         }
         Err((
-            Error::WaitTransactionTimeout(max_wait.as_secs(), start.elapsed().as_secs()),
+            Error::WaitTransactionTimeout {
+                hash: hash.to_string(),
+                max_wait: max_wait.as_secs(),
+                waited: start.elapsed().as_secs(),
+            },
             last_response,
         ))
     }
 }
 
-fn handle_response<T>(response: Response<T>) -> Result<T, Error> {
-    if let Some(result) = response.result {
-        Ok(result)
-    } else if let Some(error) = response.error {
-        Err(Error::RPCError {
-            code: error.code,
-            message: error.message.unwrap_or_default(),
+pub(crate) fn handle_response<T: DeserializeOwned>(
+    response: serde_json::Value,
+) -> Result<T, Error> {
+    T::deserialize(&response).map_err(|source| Error::JsonError {
+        operation: "decoding the result",
+        input: response.to_string(),
+        source,
+        request_id: None,
+    })
+}
+
+// Params of the RPC methods, shared with the batch requests ------------
+
+/// Check the limits of the RPC on the number of filters, contract IDs and topics
+pub(crate) fn validate_event_filters(filters: &[EventFilter]) -> Result<(), Error> {
+    let limit = |limit, count, max| match count > max {
+        true => Err(Error::EventFilterLimit { limit, count, max }),
+        false => Ok(()),
+    };
+    limit("filters per request", filters.len(), MAX_EVENT_FILTERS)?;
+    for filter in filters {
+        limit(
+            "contract IDs per filter",
+            filter.contract_ids.len(),
+            MAX_EVENT_FILTER_ITEMS,
+        )?;
+        limit(
+            "topics per filter",
+            filter.topics.len(),
+            MAX_EVENT_FILTER_ITEMS,
+        )?;
+    }
+    Ok(())
+}
+
+pub(crate) fn events_params(
+    ledger: Pagination,
+    filters: Vec<EventFilter>,
+    limit: Option<u32>,
+) -> serde_json::Value {
+    let (start_ledger, end_ledger, cursor) = match ledger {
+        Pagination::From(s) => (Some(s), None, None),
+        Pagination::FromTo(s, e) => (Some(s), Some(e), None),
+        Pagination::Cursor(c) => (None, None, Some(c)),
+    };
+    let filters = filters
+        .into_iter()
+        .map(|v| {
+            //
+            json!({
+                "type": v.event_type(),
+                "contractIds": v.contracts(),
+                "topics": v.topics(),
+            })
         })
-    } else {
-        Err(Error::UnexpectedError)
+        .collect::<Vec<serde_json::Value>>();
+
+    json!(
+    {
+        "startLedger": start_ledger,
+        "endLedger": end_ledger,
+        "filters": filters,
+        "pagination": {
+            "cursor": cursor,
+            "limit": limit
+        }
+    }
+    )
+}
+
+pub(crate) fn ledger_entries_params(keys: Vec<LedgerKey>) -> Result<serde_json::Value, Error> {
+    let keys: Result<Vec<String>, Error> = keys
+        .into_iter()
+        .map(|k| {
+            k.to_xdr_base64(Limits::none())
+                .map_err(|source| Error::XdrError {
+                    operation: "encoding a ledger key",
+                    source,
+                })
+        })
+        .collect();
+
+    Ok(json!({"keys": keys?}))
+}
+
+fn transaction_xdr(transaction: &Transaction) -> Result<String, Error> {
+    transaction
+        .to_envelope()
+        .map_err(|e| Error::TransactionError {
+            operation: "building the envelope",
+            hash: hex::encode(transaction.hash()),
+            source: Cause(e.to_string()),
+        })?
+        .to_xdr_base64(Limits::none())
+        .map_err(|source| Error::XdrError {
+            operation: "encoding the transaction envelope",
+            source,
+        })
+}
+
+pub(crate) fn paginated_params(ledger: Pagination, limit: Option<u32>) -> serde_json::Value {
+    let (start_ledger, cursor) = match ledger {
+        Pagination::From(s) => (Some(s), None),
+        Pagination::FromTo(s, _) => (Some(s), None),
+        Pagination::Cursor(c) => (None, Some(c)),
+    };
+    json!(
+    {
+        "startLedger": start_ledger,
+        "pagination": {
+            "cursor": cursor,
+            "limit": limit
+        }
     }
+    )
+}
+
+pub(crate) fn send_transaction_params(
+    transaction: &Transaction,
+) -> Result<serde_json::Value, Error> {
+    let transaction_xdr = transaction_xdr(transaction)?;
+
+    Ok(json!({
+            "transaction": transaction_xdr
+        }
+    ))
+}
+
+pub(crate) fn simulate_transaction_params(
+    transaction: &Transaction,
+    options: Option<SimulationOptions>,
+) -> Result<serde_json::Value, Error> {
+    let transaction_xdr = transaction_xdr(transaction)?;
+
+    // Add resource config if provided
+    let params = if let Some(resources) = options {
+        json!({
+            "transaction": transaction_xdr,
+            "resourceConfig": {
+                "instructionLeeway": resources.cpu_instructions
+            },
+            "authMode": resources.auth_mode.map(|a| {let mode: &str = a.into(); mode}),
+        })
+    } else {
+        json!({
+            "transaction": transaction_xdr
+        })
+    };
+    Ok(params)
 }
 
 #[cfg(test)]
@@ -1,20 +1,40 @@
 #![allow(non_snake_case)]
 use std::ops::Deref;
 
-use stellar_baselib::{
-    soroban_data_builder::{SorobanDataBuilder, SorobanDataBuilderBehavior},
-    xdr::{
-        ContractEvent, DiagnosticEvent, LedgerCloseMeta, LedgerEntry, LedgerEntryData,
-        LedgerEntryExt, LedgerHeaderHistoryEntry, LedgerKey, Limits, ReadXdr, ScVal,
-        SorobanAuthorizationEntry, SorobanTransactionData, TransactionEnvelope, TransactionEvent,
-        TransactionMeta, TransactionResult,
-    },
+use stellar_baselib::xdr::{
+    ContractEvent, ContractEventBody, DiagnosticEvent, LedgerCloseMeta, LedgerEntry,
+    LedgerEntryData, LedgerEntryExt, LedgerHeaderHistoryEntry, LedgerKey, Limits, ReadXdr, ScVal,
+    SorobanAuthorizationEntry, SorobanTransactionData, TransactionEnvelope, TransactionEvent,
+    TransactionMeta, TransactionResult,
 };
 
 use serde::{Deserialize, Serialize};
 
+use crate::error::{Cause, Error};
+use crate::events::FromContractEvent;
+use crate::failure::{ContractFailure, TransactionFailure};
+use crate::token::TokenEvent;
+
+// Decode a base64 XDR value of a response
+fn decode<T: ReadXdr>(xdr: &str, operation: &'static str) -> Result<T, Error> {
+    T::from_xdr_base64(xdr, Limits::none()).map_err(|source| Error::XdrError { operation, source })
+}
+
+// Decode an optional base64 XDR value of a response
+fn decode_opt<T: ReadXdr>(
+    xdr: Option<&String>,
+    operation: &'static str,
+) -> Result<Option<T>, Error> {
+    xdr.map(|xdr| decode(xdr, operation)).transpose()
+}
+
+// Decode a list of base64 XDR values of a response
+fn decode_all<T: ReadXdr>(xdrs: &[String], operation: &'static str) -> Result<Vec<T>, Error> {
+    xdrs.iter().map(|xdr| decode(xdr, operation)).collect()
+}
+
 /// Response to [get_health](crate::Server::get_health) RPC method
-#[derive(Debug, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
 #[serde(rename_all = "camelCase")]
 pub struct GetHealthResponse {
     /// Health status, typically 'healthy'
@@ -43,20 +63,35 @@ pub struct LedgerEntryResult {
 
 impl LedgerEntryResult {
     /// The key of the ledger entry
+    ///
+    /// Panics if the XDR is invalid, see [LedgerEntryResult::try_to_key]
     pub fn to_key(&self) -> LedgerKey {
-        LedgerKey::from_xdr_base64(&self.key, Limits::none()).expect("Invalid LedgerKey from RPC")
+        self.try_to_key().expect("Invalid LedgerKey from RPC")
+    }
+    /// The key of the ledger entry, or an error if the XDR is invalid
+    pub fn try_to_key(&self) -> Result<LedgerKey, Error> {
+        decode(&self.key, "decoding the ledger entry key")
     }
     /// The current value of the given ledger entry
+    ///
+    /// Panics if the XDR is invalid, see [LedgerEntryResult::try_to_data]
     pub fn to_data(&self) -> LedgerEntryData {
-        LedgerEntryData::from_xdr_base64(&self.xdr, Limits::none())
+        self.try_to_data()
             .expect("Invalid LedgerEntryData from RPC")
     }
+    /// The current value of the given ledger entry, or an error if the XDR is invalid
+    pub fn try_to_data(&self) -> Result<LedgerEntryData, Error> {
+        decode(&self.xdr, "decoding the ledger entry data")
+    }
     /// The extension of the ledger entry
+    ///
+    /// Panics if the XDR is invalid, see [LedgerEntryResult::try_to_ext]
     pub fn to_ext(&self) -> Option<LedgerEntryExt> {
-        self.ext_xdr.as_ref().map(|ext| {
-            LedgerEntryExt::from_xdr_base64(ext, Limits::none())
-                .expect("Invalid LedgerEntryExt from RPC")
-        })
+        self.try_to_ext().expect("Invalid LedgerEntryExt from RPC")
+    }
+    /// The extension of the ledger entry, or an error if the XDR is invalid
+    pub fn try_to_ext(&self) -> Result<Option<LedgerEntryExt>, Error> {
+        decode_opt(self.ext_xdr.as_ref(), "decoding the ledger entry extension")
     }
 }
 
@@ -135,7 +170,7 @@ impl Deref for GetTransactionResponse {
 }
 
 /// Event types (system, contract, or diagnostic) used to filter events
-#[derive(PartialEq, Eq, Deserialize, Debug)]
+#[derive(PartialEq, Eq, Deserialize, Debug, Clone)]
 #[serde(rename_all = "lowercase")]
 pub enum EventType {
     /// Only contract type events
@@ -208,16 +243,27 @@ pub struct EventResponse {
 
 impl EventResponse {
     /// List containing the topic this event was emitted with.
+    ///
+    /// Panics if the XDR is invalid, see [EventResponse::try_topic]
     pub fn topic(&self) -> Vec<ScVal> {
-        self.topic
-            .iter()
-            .map(|t| ScVal::from_xdr_base64(t, Limits::none()).expect("Invalid XDR from RPC"))
-            .collect()
+        self.try_topic().expect("Invalid XDR from RPC")
+    }
+
+    /// List containing the topic this event was emitted with, or an error if the XDR is invalid
+    pub fn try_topic(&self) -> Result<Vec<ScVal>, Error> {
+        decode_all(&self.topic, "decoding the event topic")
     }
 
     /// The emitted body value of the event (serialized in a base64 string).
+    ///
+    /// Panics if the XDR is invalid, see [EventResponse::try_value]
     pub fn value(&self) -> ScVal {
-        ScVal::from_xdr_base64(&self.value, Limits::none()).expect("Invalid XDR from RPC")
+        self.try_value().expect("Invalid XDR from RPC")
+    }
+
+    /// The emitted body value of the event, or an error if the XDR is invalid
+    pub fn try_value(&self) -> Result<ScVal, Error> {
+        decode(&self.value, "decoding the event value")
     }
 }
 
@@ -255,10 +301,27 @@ pub struct SendTransactionResponse {
 impl SendTransactionResponse {
     /// (optional) If the transaction status is [SendTransactionStatus::Error], this will be a
     /// TransactionResult struct containing details on why stellar-core rejected the transaction.
+    ///
+    /// Panics if the XDR is invalid, see [SendTransactionResponse::try_to_error_result]
     pub fn to_error_result(&self) -> Option<TransactionResult> {
-        self.error_result_xdr.as_ref().map(|e| {
-            TransactionResult::from_xdr_base64(e, Limits::none()).expect("Invalid XDR from RPC")
-        })
+        self.try_to_error_result().expect("Invalid XDR from RPC")
+    }
+
+    /// Same as [SendTransactionResponse::to_error_result], or an error if the XDR is invalid
+    pub fn try_to_error_result(&self) -> Result<Option<TransactionResult>, Error> {
+        decode_opt(
+            self.error_result_xdr.as_ref(),
+            "decoding the transaction error result",
+        )
+    }
+
+    /// (optional) Why stellar-core rejected the transaction, decoded from
+    /// [SendTransactionResponse::to_error_result], or an error if the XDR is invalid
+    pub fn try_to_failure(&self) -> Result<Option<TransactionFailure>, Error> {
+        Ok(self
+            .try_to_error_result()?
+            .as_ref()
+            .and_then(TransactionFailure::from_result))
     }
 
     /// (optional) If the transaction status is [SendTransactionStatus::Error], this field may
@@ -274,6 +337,22 @@ impl SendTransactionResponse {
             None
         }
     }
+
+    /// Same as [SendTransactionResponse::to_diagnostic_events], or an error if an XDR is invalid
+    pub fn try_to_diagnostic_events(&self) -> Result<Option<Vec<DiagnosticEvent>>, Error> {
+        self.diagnostic_events_xdr
+            .as_deref()
+            .map(|events| decode_all(events, "decoding the diagnostic events"))
+            .transpose()
+    }
+
+    /// (optional) Why the contract call failed, extracted from the diagnostic events, see
+    /// [ContractFailure], or an error if an XDR is invalid
+    pub fn try_to_contract_failure(&self) -> Result<Option<ContractFailure>, Error> {
+        Ok(self
+            .try_to_diagnostic_events()?
+            .and_then(|events| ContractFailure::from_events(&events)))
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -349,33 +428,57 @@ impl SimulateTransactionResponse {
     /// (optional) - This array will only have one element: the result for the Host Function
     /// invocation.
     /// Only present on successful simulation (i.e. no error) of InvokeHostFunction op
+    ///
+    /// Panics if the XDR is invalid, see [SimulateTransactionResponse::try_to_result]
     pub fn to_result(&self) -> Option<(ScVal, Vec<SorobanAuthorizationEntry>)> {
-        if let Some(r) = self.results.as_ref() {
-            let auth: Vec<SorobanAuthorizationEntry> = r[0]
-                .auth
-                .iter()
-                .map(|e| SorobanAuthorizationEntry::from_xdr_base64(e, Limits::none()).unwrap())
-                .collect();
-            let ret_val = ScVal::from_xdr_base64(&r[0].xdr, Limits::none())
-                .expect("Xdr from RPC should be valid");
+        self.try_to_result().expect("Xdr from RPC should be valid")
+    }
 
-            Some((ret_val, auth))
-        } else {
-            None
-        }
+    /// Same as [SimulateTransactionResponse::to_result], or an error if the XDR is invalid
+    pub fn try_to_result(&self) -> Result<Option<(ScVal, Vec<SorobanAuthorizationEntry>)>, Error> {
+        let Some(r) = self.results.as_ref().and_then(|r| r.first()) else {
+            return Ok(None);
+        };
+        let auth = decode_all(&r.auth, "decoding the simulation auth")?;
+        let ret_val = decode(&r.xdr, "decoding the simulation return value")?;
+        Ok(Some((ret_val, auth)))
     }
 
     /// (optional) - The recommended Soroban Transaction Data to use when
     /// submitting the simulated transaction. This data contains the refundable fee and resource
     /// usage information such as the ledger footprint and IO access data.
     /// Not present in case of error.
+    ///
+    /// Panics if the XDR is invalid, see [SimulateTransactionResponse::try_to_transaction_data]
     pub fn to_transaction_data(&self) -> Option<SorobanTransactionData> {
-        self.transaction_data.as_ref().map(|data| {
-            SorobanDataBuilder::new(Some(stellar_baselib::soroban_data_builder::Either::Left(
-                data.to_owned(),
-            )))
-            .build()
-        })
+        self.try_to_transaction_data()
+            .expect("Invalid SorobanTransactionData from RPC")
+    }
+
+    /// Same as [SimulateTransactionResponse::to_transaction_data], or an error if the XDR is
+    /// invalid
+    pub fn try_to_transaction_data(&self) -> Result<Option<SorobanTransactionData>, Error> {
+        decode_opt(
+            self.transaction_data.as_ref(),
+            "decoding the simulation transaction data",
+        )
+    }
+
+    /// The [SimulateTransactionResponse::min_resource_fee] as a number, or an error if it is
+    /// missing or invalid
+    pub fn try_min_resource_fee(&self) -> Result<i64, Error> {
+        let fee = self
+            .min_resource_fee
+            .as_deref()
+            .ok_or_else(|| Error::InvalidResponse {
+                field: "minResourceFee",
+                source: Cause("missing".to_string()).into(),
+            })?;
+        fee.parse()
+            .map_err(|e: std::num::ParseIntError| Error::InvalidResponse {
+                field: "minResourceFee",
+                source: e.into(),
+            })
     }
 
     /// (optional) - It can only be present on successful simulation (i.e. no error) of
@@ -425,21 +528,35 @@ impl SimulateTransactionResponse {
     ///     }
     /// # }
     /// ```
+    ///
+    /// Panics if the response is invalid, see
+    /// [SimulateTransactionResponse::try_to_restore_transaction_data]
     pub fn to_restore_transaction_data(&self) -> Option<(i64, SorobanTransactionData)> {
-        if let Some(restore) = self.restore_preamble.clone() {
-            Some((
-                restore
-                    .min_resource_fee
-                    .parse()
-                    .expect("Invalid i64 for min_resource_fee"),
-                SorobanDataBuilder::new(Some(stellar_baselib::soroban_data_builder::Either::Left(
-                    restore.transaction_data,
-                )))
-                .build(),
-            ))
-        } else {
-            None
-        }
+        self.try_to_restore_transaction_data()
+            .expect("Invalid restore preamble from RPC")
+    }
+
+    /// Same as [SimulateTransactionResponse::to_restore_transaction_data], or an error if the
+    /// response is invalid
+    pub fn try_to_restore_transaction_data(
+        &self,
+    ) -> Result<Option<(i64, SorobanTransactionData)>, Error> {
+        let Some(restore) = self.restore_preamble.as_ref() else {
+            return Ok(None);
+        };
+        let min_resource_fee =
+            restore
+                .min_resource_fee
+                .parse()
+                .map_err(|e: std::num::ParseIntError| Error::InvalidResponse {
+                    field: "restorePreamble.minResourceFee",
+                    source: e.into(),
+                })?;
+        let data = decode(
+            &restore.transaction_data,
+            "decoding the restore transaction data",
+        )?;
+        Ok(Some((min_resource_fee, data)))
     }
 
     /// (optional) - Array of the events emitted during the
@@ -457,32 +574,50 @@ impl SimulateTransactionResponse {
         }
     }
 
+    /// Same as [SimulateTransactionResponse::to_events], or an error if an XDR is invalid
+    pub fn try_to_events(&self) -> Result<Option<Vec<DiagnosticEvent>>, Error> {
+        self.events
+            .as_deref()
+            .map(|events| decode_all(events, "decoding the simulation events"))
+            .transpose()
+    }
+
+    /// (optional) Why the simulated contract call failed, extracted from the events, see
+    /// [ContractFailure], or an error if an XDR is invalid
+    pub fn try_to_contract_failure(&self) -> Result<Option<ContractFailure>, Error> {
+        Ok(self
+            .try_to_events()?
+            .and_then(|events| ContractFailure::from_events(&events)))
+    }
+
     /// (optional) - On successful simulation of InvokeHostFunction operations, this field will be
     /// an array of LedgerEntrys before and after simulation occurred. Note that at least one of
     /// before or after will be present: before and no after indicates a deletion event, the
     /// inverse is a creation event, and both present indicates an update event. Or just check the
     /// type.
+    ///
+    /// Panics if the XDR is invalid, see [SimulateTransactionResponse::try_to_state_changes]
     pub fn to_state_changes(&self) -> Vec<StateChange> {
-        if let Some(changes) = self.state_changes.as_ref() {
-            changes
-                .iter()
-                .map(|c| StateChange {
+        self.try_to_state_changes()
+            .expect("Invalid state changes from RPC")
+    }
+
+    /// Same as [SimulateTransactionResponse::to_state_changes], or an error if the XDR is invalid
+    pub fn try_to_state_changes(&self) -> Result<Vec<StateChange>, Error> {
+        let Some(changes) = self.state_changes.as_ref() else {
+            return Ok(Vec::new());
+        };
+        changes
+            .iter()
+            .map(|c| {
+                Ok(StateChange {
                     kind: c.kind,
-                    key: LedgerKey::from_xdr_base64(&c.key, Limits::none())
-                        .expect("Invalid LedgerKey"),
-                    before: c.before.as_ref().map(|e| {
-                        LedgerEntry::from_xdr_base64(e, Limits::none())
-                            .expect("Invalid LedgerEntry")
-                    }),
-                    after: c.after.as_ref().map(|e| {
-                        LedgerEntry::from_xdr_base64(e, Limits::none())
-                            .expect("Invalid LedgerEntry")
-                    }),
+                    key: decode(&c.key, "decoding the state change key")?,
+                    before: decode_opt(c.before.as_ref(), "decoding the state change entry")?,
+                    after: decode_opt(c.after.as_ref(), "decoding the state change entry")?,
                 })
-                .collect()
-        } else {
-            Default::default()
-        }
+            })
+            .collect()
     }
 }
 
@@ -716,6 +851,110 @@ impl TransactionDetails {
             None
         }
     }
+
+    /// Same as [TransactionDetails::to_envelope], or an error if the XDR is invalid
+    pub fn try_to_envelope(&self) -> Result<Option<TransactionEnvelope>, Error> {
+        decode_opt(
+            self.envelope_xdr.as_ref(),
+            "decoding the transaction envelope",
+        )
+    }
+
+    /// Same as [TransactionDetails::to_result], or an error if the XDR is invalid
+    pub fn try_to_result(&self) -> Result<Option<TransactionResult>, Error> {
+        decode_opt(self.result_xdr.as_ref(), "decoding the transaction result")
+    }
+
+    /// (optional) Why the transaction failed, decoded from [TransactionDetails::to_result], or an
+    /// error if the XDR is invalid. `None` if the transaction succeeded.
+    pub fn try_to_failure(&self) -> Result<Option<TransactionFailure>, Error> {
+        Ok(self
+            .try_to_result()?
+            .as_ref()
+            .and_then(TransactionFailure::from_result))
+    }
+
+    /// Same as [TransactionDetails::to_result_meta], or an error if the XDR is invalid
+    pub fn try_to_result_meta(&self) -> Result<Option<(TransactionMeta, Option<ScVal>)>, Error> {
+        let Some(meta) = decode_opt::<TransactionMeta>(
+            self.result_meta_xdr.as_ref(),
+            "decoding the transaction meta",
+        )?
+        else {
+            return Ok(None);
+        };
+        let return_value = match &meta {
+            TransactionMeta::V3(v3) => v3.soroban_meta.as_ref().map(|v| v.return_value.clone()),
+            TransactionMeta::V4(v4) => v4
+                .soroban_meta
+                .as_ref()
+                .and_then(|v| v.return_value.clone()),
+            _ => None,
+        };
+        Ok(Some((meta, return_value)))
+    }
+
+    /// Same as [TransactionDetails::to_diagnostic_events], or an error if an XDR is invalid
+    pub fn try_to_diagnostic_events(&self) -> Result<Option<Vec<DiagnosticEvent>>, Error> {
+        self.diagnostic_events_xdr
+            .as_deref()
+            .map(|events| decode_all(events, "decoding the diagnostic events"))
+            .transpose()
+    }
+
+    /// (optional) Why the contract call failed, extracted from the diagnostic events, see
+    /// [ContractFailure], or an error if an XDR is invalid
+    pub fn try_to_contract_failure(&self) -> Result<Option<ContractFailure>, Error> {
+        Ok(self
+            .try_to_diagnostic_events()?
+            .and_then(|events| ContractFailure::from_events(&events)))
+    }
+
+    /// Same as [TransactionDetails::to_events], or an error if an XDR is invalid
+    #[allow(clippy::type_complexity)]
+    pub fn try_to_events(
+        &self,
+    ) -> Result<Option<(Vec<TransactionEvent>, Vec<Vec<ContractEvent>>)>, Error> {
+        let Some(events) = &self.events else {
+            return Ok(None);
+        };
+        let tx_events = match &events.transaction_events_xdr {
+            Some(te) => decode_all(te, "decoding the transaction events")?,
+            None => Vec::default(),
+        };
+        let cx_events = match &events.contract_events_xdr {
+            Some(ce) => ce
+                .iter()
+                .map(|row| decode_all(row, "decoding the contract events"))
+                .collect::<Result<_, _>>()?,
+            None => Vec::default(),
+        };
+        Ok(Some((tx_events, cx_events)))
+    }
+
+    /// (optional) Token events of the contract events, with the strkey of the token contract,
+    /// see [TokenEvent]. The events which are not token events are skipped, including the
+    /// events named like a token event but with another shape, such as an NFT `transfer`. An
+    /// invalid XDR is an error.
+    pub fn try_to_token_events(&self) -> Result<Option<Vec<(String, TokenEvent)>>, Error> {
+        let Some((_, contract_events)) = self.try_to_events()? else {
+            return Ok(None);
+        };
+        let token_events = contract_events
+            .iter()
+            .flatten()
+            .filter_map(|event| {
+                let contract = event.contract_id.as_ref()?;
+                let ContractEventBody::V0(body) = &event.body;
+                if !TokenEvent::is_token_event(&body.topics) {
+                    return None;
+                }
+                let token_event = TokenEvent::from_xdr_event(event).ok()?;
+                Some((contract.to_string(), token_event))
+            })
+            .collect();
+        Ok(Some(token_events))
+    }
 }
 
 /// Response to [get_ledgers](crate::Server::get_ledgers)
@@ -752,16 +991,23 @@ pub struct LedgerInfo {
 
 impl LedgerInfo {
     /// LedgerHeader for this ledger
+    ///
+    /// Panics if the XDR is invalid, see [LedgerInfo::try_to_header]
     pub fn to_header(&self) -> Option<LedgerHeaderHistoryEntry> {
-        self.header_xdr.as_ref().map(|header| {
-            LedgerHeaderHistoryEntry::from_xdr_base64(header, Limits::none())
-                .expect("Invalid XDR from RPC")
-        })
+        self.try_to_header().expect("Invalid XDR from RPC")
+    }
+    /// LedgerHeader for this ledger, or an error if the XDR is invalid
+    pub fn try_to_header(&self) -> Result<Option<LedgerHeaderHistoryEntry>, Error> {
+        decode_opt(self.header_xdr.as_ref(), "decoding the ledger header")
     }
     /// LedgerCloseMeta for this ledger
+    ///
+    /// Panics if the XDR is invalid, see [LedgerInfo::try_to_metadata]
     pub fn to_metadata(&self) -> Option<LedgerCloseMeta> {
-        self.metadataXdr.as_ref().map(|meta| {
-            LedgerCloseMeta::from_xdr_base64(meta, Limits::none()).expect("Invalid XDR from RPC")
-        })
+        self.try_to_metadata().expect("Invalid XDR from RPC")
+    }
+    /// LedgerCloseMeta for this ledger, or an error if the XDR is invalid
+    pub fn try_to_metadata(&self) -> Result<Option<LedgerCloseMeta>, Error> {
+        decode_opt(self.metadataXdr.as_ref(), "decoding the ledger metadata")
     }
 }
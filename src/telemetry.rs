@@ -0,0 +1,65 @@
+use std::time::Duration;
+
+use tracing::field::Empty;
+use tracing::Span;
+
+use crate::error::Error;
+
+/// Span of a call to the RPC `method`
+///
+/// The transport records the `endpoint` and `request_id` fields, [record] the others.
+pub fn rpc_span(method: &str) -> Span {
+    tracing::info_span!(
+        "rpc",
+        method,
+        endpoint = Empty,
+        request_id = Empty,
+        duration_ms = Empty,
+        outcome = Empty,
+        rpc_code = Empty,
+    )
+}
+
+/// Record the end of the call in its `span`, and in the metrics with the `metrics` feature
+pub fn record<T>(span: &Span, method: &str, elapsed: Duration, result: &Result<T, Error>) {
+    let (outcome, rpc_code) = match result {
+        Ok(_) => ("success", None),
+        Err(Error::RPCError { code, .. }) => ("rpc_error", Some(*code)),
+        Err(_) => ("error", None),
+    };
+    span.record("duration_ms", elapsed.as_millis() as u64);
+    span.record("outcome", outcome);
+    if let Some(code) = rpc_code {
+        span.record("rpc_code", code);
+    }
+    match result {
+        Ok(_) => tracing::debug!(parent: span, "RPC call succeeded"),
+        Err(e) => tracing::warn!(parent: span, error = %e, "RPC call failed"),
+    }
+
+    #[cfg(feature = "metrics")]
+    {
+        let method = method.to_string();
+        metrics::counter!(
+            "soroban_client_requests_total",
+            "method" => method.clone(),
+            "outcome" => outcome
+        )
+        .increment(1);
+        metrics::histogram!(
+            "soroban_client_request_duration_seconds",
+            "method" => method.clone()
+        )
+        .record(elapsed.as_secs_f64());
+        if let Some(code) = rpc_code {
+            metrics::counter!(
+                "soroban_client_rpc_errors_total",
+                "method" => method,
+                "code" => code.to_string()
+            )
+            .increment(1);
+        }
+    }
+    #[cfg(not(feature = "metrics"))]
+    let _ = method;
+}
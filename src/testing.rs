@@ -0,0 +1,689 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::sync::Mutex;
+
+use serde_json::{json, Value};
+use stellar_baselib::hashing::{HashingBehavior, Sha256Hasher};
+use stellar_baselib::keypair::{Keypair, KeypairBehavior};
+use stellar_baselib::network::{NetworkPassphrase, Networks};
+use stellar_baselib::xdr::{
+    AccountEntry, AccountEntryExt, AccountId, Hash, LedgerEntryData, LedgerKey, LedgerKeyAccount,
+    Limits, MuxedAccount, PublicKey, ReadXdr, ScVal, SequenceNumber, SorobanTransactionData,
+    String32, Thresholds, TransactionEnvelope, TransactionResult, TransactionResultExt,
+    TransactionResultResult, TransactionSignaturePayload,
+    TransactionSignaturePayloadTaggedTransaction, Uint256, VecM, WriteXdr,
+};
+
+use crate::error::Error;
+use crate::transport::{async_trait, RpcTransport};
+
+const CLOSE_TIME_ORIGIN: u64 = 1_700_000_000;
+const LEDGER_CLOSE_SECONDS: u64 = 5;
+const RETENTION_WINDOW: u32 = 17280;
+
+/// In-memory fake of the Stellar RPC, to test code using a [Server](crate::Server) without network
+///
+/// The fake keeps a ledger sequence, ledger entries, submitted transactions and contract events:
+/// - `getHealth`, `getNetwork` and `getLatestLedger` describe the current ledger
+/// - `getLedgerEntries` returns the entries set with [FakeRpc::set_ledger_entry]
+/// - `sendTransaction` checks the sequence number of the source account, if its entry exists,
+///   and bumps it. The transaction is included in the next ledger
+/// - `getTransaction` returns `SUCCESS` once the inclusion ledger is reached, `NOT_FOUND` before
+/// - `getEvents` returns the events emitted with [FakeRpc::emit_event], filtered by ledger,
+///   type, contract and topics
+/// - `simulateTransaction` succeeds with a `Void` result and empty resources
+///
+/// Each call closes [FakeRpc::set_ledgers_per_call] ledgers (1 by default) before it is handled,
+/// so a submitted transaction can be waited for. Any method can be scripted with
+/// [FakeRpc::push_response], other methods return a "method not found" [Error::RPCError].
+///
+/// # Example
+/// ```rust
+/// # use std::sync::Arc;
+/// # use soroban_client::*;
+/// # use soroban_client::error::Error;
+/// use soroban_client::testing::FakeRpc;
+///
+/// # async fn run() -> Result<(), Error> {
+/// let fake = Arc::new(FakeRpc::default());
+/// fake.set_account("GAQODVWAY3AYAGEAT4CG3YSPM4FBTBB2QSXCYJLM3HVIV5ILTP5BRXCD", 10);
+///
+/// let server = Server::with_transport(fake.clone(), Options::default());
+/// let account = server
+///     .get_account("GAQODVWAY3AYAGEAT4CG3YSPM4FBTBB2QSXCYJLM3HVIV5ILTP5BRXCD")
+///     .await?;
+/// # Ok(()) }
+/// ```
+#[derive(Debug)]
+pub struct FakeRpc {
+    state: Mutex<State>,
+}
+
+#[derive(Debug)]
+struct State {
+    passphrase: String,
+    protocol_version: u32,
+    latest_ledger: u32,
+    ledgers_per_call: u32,
+    entries: BTreeMap<String, Entry>,
+    transactions: HashMap<String, Submitted>,
+    events: Vec<Value>,
+    responses: HashMap<String, VecDeque<Result<Value, Error>>>,
+    calls: Vec<(String, Value)>,
+}
+
+#[derive(Debug)]
+struct Entry {
+    xdr: String,
+    last_modified: u32,
+    live_until: Option<u32>,
+}
+
+#[derive(Debug)]
+struct Submitted {
+    envelope_xdr: String,
+    result_xdr: String,
+    ledger: u32,
+    application_order: u32,
+    fee_bump: bool,
+}
+
+impl Default for FakeRpc {
+    fn default() -> Self {
+        FakeRpc::new(Networks::testnet())
+    }
+}
+
+impl FakeRpc {
+    /// Create a fake RPC serving the network with this `passphrase`, starting at ledger 1
+    pub fn new(passphrase: &str) -> Self {
+        FakeRpc {
+            state: Mutex::new(State {
+                passphrase: passphrase.to_string(),
+                protocol_version: 23,
+                latest_ledger: 1,
+                ledgers_per_call: 1,
+                entries: BTreeMap::new(),
+                transactions: HashMap::new(),
+                events: Vec::new(),
+                responses: HashMap::new(),
+                calls: Vec::new(),
+            }),
+        }
+    }
+
+    /// Sequence number of the latest ledger
+    pub fn latest_ledger(&self) -> u32 {
+        self.state.lock().unwrap().latest_ledger
+    }
+
+    /// Set the sequence number of the latest ledger
+    pub fn set_latest_ledger(&self, sequence: u32) {
+        self.state.lock().unwrap().latest_ledger = sequence;
+    }
+
+    /// Close `count` ledgers
+    pub fn advance(&self, count: u32) {
+        self.state.lock().unwrap().latest_ledger += count;
+    }
+
+    /// Number of ledgers closed before each call is handled, 0 to stop the ledgers
+    pub fn set_ledgers_per_call(&self, count: u32) {
+        self.state.lock().unwrap().ledgers_per_call = count;
+    }
+
+    /// Store the ledger entry `data` at `key`, replacing the previous one
+    pub fn set_ledger_entry(
+        &self,
+        key: &LedgerKey,
+        data: &LedgerEntryData,
+        live_until: Option<u32>,
+    ) {
+        let mut state = self.state.lock().unwrap();
+        let entry = Entry {
+            xdr: data
+                .to_xdr_base64(Limits::none())
+                .expect("Invalid LedgerEntryData"),
+            last_modified: state.latest_ledger,
+            live_until,
+        };
+        state.entries.insert(key_xdr(key), entry);
+    }
+
+    /// Remove the ledger entry at `key`
+    pub fn remove_ledger_entry(&self, key: &LedgerKey) {
+        self.state.lock().unwrap().entries.remove(&key_xdr(key));
+    }
+
+    /// Store an account entry for `address`, with `sequence` as sequence number
+    ///
+    /// # Panics
+    ///
+    /// If `address` is not a valid account strkey (`G...`).
+    pub fn set_account(&self, address: &str, sequence: i64) {
+        let account_id = Keypair::from_public_key(address)
+            .expect("Invalid account address")
+            .xdr_account_id();
+        let key = LedgerKey::Account(LedgerKeyAccount {
+            account_id: account_id.clone(),
+        });
+        let data = LedgerEntryData::Account(AccountEntry {
+            account_id,
+            balance: 10_000_000_000,
+            seq_num: SequenceNumber(sequence),
+            num_sub_entries: 0,
+            inflation_dest: None,
+            flags: 0,
+            home_domain: String32::default(),
+            thresholds: Thresholds([1, 0, 0, 0]),
+            signers: VecM::default(),
+            ext: AccountEntryExt::V0,
+        });
+        self.set_ledger_entry(&key, &data, None);
+    }
+
+    /// Emit a contract event in the latest ledger
+    ///
+    /// The event is returned by `getEvents`, with a fake transaction hash.
+    pub fn emit_event(&self, contract_id: &str, topic: Vec<ScVal>, value: ScVal) {
+        let mut state = self.state.lock().unwrap();
+        let ledger = state.latest_ledger;
+        let index = state
+            .events
+            .iter()
+            .filter(|e| e["ledger"] == ledger)
+            .count();
+        let topic: Vec<String> = topic
+            .iter()
+            .map(|t| t.to_xdr_base64(Limits::none()).expect("Invalid ScVal"))
+            .collect();
+        let id = format!("{:019}-{:010}", u64::from(ledger) << 32, index);
+        state.events.push(json!({
+            "type": "contract",
+            "ledger": ledger,
+            "ledgerClosedAt": close_time(ledger).to_string(),
+            "contractId": contract_id,
+            "id": id,
+            "pagingToken": id,
+            "operationIndex": 0,
+            "transactionIndex": 0,
+            "txHash": hex::encode(Sha256Hasher::hash(id.as_bytes())),
+            "inSuccessfulContractCall": true,
+            "topic": topic,
+            "value": value.to_xdr_base64(Limits::none()).expect("Invalid ScVal"),
+        }));
+    }
+
+    /// Queue the `response` of the next call to `method`, before the default behaviour
+    ///
+    /// Several responses for the same method are returned in the order they were pushed.
+    pub fn push_response(&self, method: &str, response: Result<Value, Error>) {
+        self.state
+            .lock()
+            .unwrap()
+            .responses
+            .entry(method.to_string())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// Calls received so far, with their params
+    pub fn calls(&self) -> Vec<(String, Value)> {
+        self.state.lock().unwrap().calls.clone()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl RpcTransport for FakeRpc {
+    async fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+        let mut state = self.state.lock().unwrap();
+        state.latest_ledger += state.ledgers_per_call;
+        state.calls.push((method.to_string(), params.clone()));
+
+        if let Some(response) = state
+            .responses
+            .get_mut(method)
+            .and_then(VecDeque::pop_front)
+        {
+            return response;
+        }
+
+        match method {
+            "getHealth" => Ok(json!({
+                "status": "healthy",
+                "latestLedger": state.latest_ledger,
+                "oldestLedger": state.oldest_ledger(),
+                "ledgerRetentionWindow": RETENTION_WINDOW,
+            })),
+            "getNetwork" => Ok(json!({
+                "passphrase": state.passphrase,
+                "protocolVersion": state.protocol_version,
+            })),
+            "getLatestLedger" => Ok(json!({
+                "id": hex::encode(Sha256Hasher::hash(state.latest_ledger.to_be_bytes())),
+                "protocolVersion": state.protocol_version,
+                "sequence": state.latest_ledger,
+            })),
+            "getLedgerEntries" => state.get_ledger_entries(&params),
+            "sendTransaction" => state.send_transaction(&params),
+            "getTransaction" => state.get_transaction(&params),
+            "getEvents" => state.get_events(&params),
+            "simulateTransaction" => Ok(state.simulate_transaction()),
+            _ => Err(Error::RPCError {
+                code: -32601,
+                message: "method not found".to_string(),
+                data: None,
+                request_id: None,
+            }),
+        }
+    }
+}
+
+impl State {
+    fn oldest_ledger(&self) -> u32 {
+        self.latest_ledger
+            .saturating_sub(RETENTION_WINDOW)
+            .saturating_add(1)
+    }
+
+    fn get_ledger_entries(&self, params: &Value) -> Result<Value, Error> {
+        let keys = params["keys"]
+            .as_array()
+            .ok_or_else(|| invalid_params("keys must be an array"))?;
+        let entries: Vec<Value> = keys
+            .iter()
+            .filter_map(|key| {
+                let key = key.as_str()?;
+                self.entries.get(key).map(|entry| {
+                    json!({
+                        "key": key,
+                        "xdr": entry.xdr,
+                        "lastModifiedLedgerSeq": entry.last_modified,
+                        "liveUntilLedgerSeq": entry.live_until,
+                    })
+                })
+            })
+            .collect();
+        Ok(json!({
+            "entries": entries,
+            "latestLedger": self.latest_ledger,
+        }))
+    }
+
+    fn send_transaction(&mut self, params: &Value) -> Result<Value, Error> {
+        let envelope_xdr = params["transaction"]
+            .as_str()
+            .ok_or_else(|| invalid_params("missing transaction"))?;
+        let envelope = TransactionEnvelope::from_xdr_base64(envelope_xdr, Limits::none())
+            .map_err(|e| invalid_params(&format!("invalid transaction: {e}")))?;
+        let hash = transaction_hash(&envelope, &self.passphrase)
+            .ok_or_else(|| invalid_params("unsupported envelope type"))?;
+
+        let mut response = json!({
+            "hash": hash,
+            "latestLedger": self.latest_ledger,
+            "latestLedgerCloseTime": close_time(self.latest_ledger).to_string(),
+        });
+        if self.transactions.contains_key(&hash) {
+            response["status"] = json!("DUPLICATE");
+            return Ok(response);
+        }
+
+        let (source, seq_num, fee, fee_bump) = match &envelope {
+            TransactionEnvelope::Tx(e) => (&e.tx.source_account, e.tx.seq_num.0, e.tx.fee, false),
+            TransactionEnvelope::TxFeeBump(e) => {
+                let stellar_baselib::xdr::FeeBumpTransactionInnerTx::Tx(inner) = &e.tx.inner_tx;
+                (
+                    &inner.tx.source_account,
+                    inner.tx.seq_num.0,
+                    inner.tx.fee,
+                    true,
+                )
+            }
+            TransactionEnvelope::TxV0(_) => unreachable!("rejected by transaction_hash"),
+        };
+        if let Err(result) = self.bump_sequence(source, seq_num) {
+            response["status"] = json!("ERROR");
+            response["errorResultXdr"] = json!(result);
+            return Ok(response);
+        }
+
+        let ledger = self.latest_ledger + 1;
+        let application_order = self
+            .transactions
+            .values()
+            .filter(|t| t.ledger == ledger)
+            .count() as u32
+            + 1;
+        let result = TransactionResult {
+            fee_charged: i64::from(fee),
+            result: TransactionResultResult::TxSuccess(VecM::default()),
+            ext: TransactionResultExt::V0,
+        };
+        self.transactions.insert(
+            hash,
+            Submitted {
+                envelope_xdr: envelope_xdr.to_string(),
+                result_xdr: result
+                    .to_xdr_base64(Limits::none())
+                    .expect("Invalid result"),
+                ledger,
+                application_order,
+                fee_bump,
+            },
+        );
+        response["status"] = json!("PENDING");
+        Ok(response)
+    }
+
+    // Check and bump the sequence number of the source account, when its entry is known
+    fn bump_sequence(&mut self, source: &MuxedAccount, seq_num: i64) -> Result<(), String> {
+        let ed25519 = match source {
+            MuxedAccount::Ed25519(key) => key.clone(),
+            MuxedAccount::MuxedEd25519(muxed) => muxed.ed25519.clone(),
+        };
+        let key = key_xdr(&LedgerKey::Account(LedgerKeyAccount {
+            account_id: AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(ed25519.0))),
+        }));
+        let latest_ledger = self.latest_ledger;
+        let Some(entry) = self.entries.get_mut(&key) else {
+            return Ok(());
+        };
+        let Ok(LedgerEntryData::Account(mut account)) =
+            LedgerEntryData::from_xdr_base64(&entry.xdr, Limits::none())
+        else {
+            return Ok(());
+        };
+        if seq_num != account.seq_num.0 + 1 {
+            let result = TransactionResult {
+                fee_charged: 0,
+                result: TransactionResultResult::TxBadSeq,
+                ext: TransactionResultExt::V0,
+            };
+            return Err(result
+                .to_xdr_base64(Limits::none())
+                .expect("Invalid result"));
+        }
+        account.seq_num = SequenceNumber(seq_num);
+        entry.xdr = LedgerEntryData::Account(account)
+            .to_xdr_base64(Limits::none())
+            .expect("Invalid account");
+        entry.last_modified = latest_ledger + 1;
+        Ok(())
+    }
+
+    fn get_transaction(&self, params: &Value) -> Result<Value, Error> {
+        let hash = params["hash"]
+            .as_str()
+            .ok_or_else(|| invalid_params("missing hash"))?;
+        let mut response = json!({
+            "status": "NOT_FOUND",
+            "latestLedger": self.latest_ledger,
+            "latestLedgerCloseTime": close_time(self.latest_ledger).to_string(),
+            "oldestLedger": self.oldest_ledger(),
+            "oldestLedgerCloseTime": close_time(self.oldest_ledger()).to_string(),
+        });
+        if let Some(tx) = self
+            .transactions
+            .get(hash)
+            .filter(|tx| tx.ledger <= self.latest_ledger)
+        {
+            response["status"] = json!("SUCCESS");
+            response["txHash"] = json!(hash);
+            response["ledger"] = json!(tx.ledger);
+            response["createdAt"] = json!(close_time(tx.ledger).to_string());
+            response["applicationOrder"] = json!(tx.application_order);
+            response["feeBump"] = json!(tx.fee_bump);
+            response["envelopeXdr"] = json!(tx.envelope_xdr);
+            response["resultXdr"] = json!(tx.result_xdr);
+        }
+        Ok(response)
+    }
+
+    fn get_events(&self, params: &Value) -> Result<Value, Error> {
+        let cursor = params["pagination"]["cursor"].as_str();
+        let limit = params["pagination"]["limit"].as_u64().unwrap_or(100) as usize;
+        let start = params["startLedger"].as_u64();
+        let end = params["endLedger"].as_u64();
+        if cursor.is_none() && start.is_none() {
+            return Err(invalid_params("startLedger or cursor is required"));
+        }
+        let filters = params["filters"].as_array().cloned().unwrap_or_default();
+
+        let events: Vec<&Value> = self
+            .events
+            .iter()
+            .filter(|e| {
+                let ledger = e["ledger"].as_u64().unwrap_or_default();
+                match cursor {
+                    Some(cursor) => e["id"].as_str().unwrap_or_default() > cursor,
+                    None => ledger >= start.unwrap_or_default(),
+                }
+            })
+            .filter(|e| end.is_none() || e["ledger"].as_u64() < end)
+            .filter(|e| filters.is_empty() || filters.iter().any(|f| matches_filter(e, f)))
+            .take(limit)
+            .collect();
+        let cursor = events
+            .last()
+            .and_then(|e| e["id"].as_str())
+            .unwrap_or_default();
+
+        Ok(json!({
+            "events": events,
+            "cursor": cursor,
+            "latestLedger": self.latest_ledger,
+            "oldestLedger": self.oldest_ledger(),
+            "latestLedgerCloseTime": close_time(self.latest_ledger).to_string(),
+            "oldestLedgerCloseTime": close_time(self.oldest_ledger()).to_string(),
+        }))
+    }
+
+    fn simulate_transaction(&self) -> Value {
+        json!({
+            "latestLedger": self.latest_ledger,
+            "minResourceFee": "0",
+            "transactionData": SorobanTransactionData::default()
+                .to_xdr_base64(Limits::none())
+                .expect("Invalid transaction data"),
+            "results": [{
+                "auth": [],
+                "xdr": ScVal::Void.to_xdr_base64(Limits::none()).expect("Invalid ScVal"),
+            }],
+        })
+    }
+}
+
+// Filter with the JSON format sent by `Server::get_events`
+fn matches_filter(event: &Value, filter: &Value) -> bool {
+    if let Some(event_type) = filter["type"].as_str() {
+        if event["type"] != event_type {
+            return false;
+        }
+    }
+    if let Some(contracts) = filter["contractIds"].as_array() {
+        if !contracts.is_empty() && !contracts.contains(&event["contractId"]) {
+            return false;
+        }
+    }
+    let topic = event["topic"].as_array().cloned().unwrap_or_default();
+    match filter["topics"].as_array() {
+        Some(topics) if !topics.is_empty() => topics.iter().any(|t| {
+            let segments = t.as_array().cloned().unwrap_or_default();
+            matches_topic(&topic, &segments)
+        }),
+        _ => true,
+    }
+}
+
+fn matches_topic(topic: &[Value], segments: &[Value]) -> bool {
+    match (segments.first().and_then(Value::as_str), topic.first()) {
+        (Some("**"), _) => true,
+        (None, None) => true,
+        (Some("*"), Some(_)) => matches_topic(&topic[1..], &segments[1..]),
+        (Some(segment), Some(value)) if value == segment => {
+            matches_topic(&topic[1..], &segments[1..])
+        }
+        _ => false,
+    }
+}
+
+fn transaction_hash(envelope: &TransactionEnvelope, passphrase: &str) -> Option<String> {
+    let tagged_transaction = match envelope {
+        TransactionEnvelope::Tx(e) => {
+            TransactionSignaturePayloadTaggedTransaction::Tx(e.tx.clone())
+        }
+        TransactionEnvelope::TxFeeBump(e) => {
+            TransactionSignaturePayloadTaggedTransaction::TxFeeBump(e.tx.clone())
+        }
+        TransactionEnvelope::TxV0(_) => return None,
+    };
+    let payload = TransactionSignaturePayload {
+        network_id: Hash(Sha256Hasher::hash(passphrase.as_bytes())),
+        tagged_transaction,
+    };
+    let payload = payload.to_xdr(Limits::none()).ok()?;
+    Some(hex::encode(Sha256Hasher::hash(payload)))
+}
+
+fn key_xdr(key: &LedgerKey) -> String {
+    key.to_xdr_base64(Limits::none())
+        .expect("Invalid LedgerKey")
+}
+
+fn close_time(ledger: u32) -> u64 {
+    CLOSE_TIME_ORIGIN + u64::from(ledger) * LEDGER_CLOSE_SECONDS
+}
+
+fn invalid_params(message: &str) -> Error {
+    Error::RPCError {
+        code: -32602,
+        message: message.to_string(),
+        data: None,
+        request_id: None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use stellar_baselib::account::AccountBehavior;
+    use stellar_baselib::contract::{ContractBehavior, Contracts};
+    use stellar_baselib::transaction::TransactionBehavior;
+    use stellar_baselib::transaction_builder::{TransactionBuilder, TransactionBuilderBehavior};
+
+    use super::*;
+    use crate::soroban_rpc::{EventType, SendTransactionStatus, TransactionStatus};
+    use crate::{EventFilter, Options, Pagination, Server, Topic};
+
+    const CONTRACT: &str = "CDGAH7TU7UH3BXGYXRIXLJX63LYRIF6APZPIG64ZAW3NNDCPJ7AAWVTZ";
+
+    #[tokio::test]
+    async fn submit_and_wait() {
+        let keypair = Keypair::random().unwrap();
+        let fake = Arc::new(FakeRpc::default());
+        fake.set_account(&keypair.public_key(), 41);
+        let server = Server::with_transport(fake.clone(), Options::default());
+
+        let mut account = server.get_account(&keypair.public_key()).await.unwrap();
+        let op = Contracts::new(CONTRACT)
+            .unwrap()
+            .call("increment", Some(vec![ScVal::U32(3)]));
+        let mut builder = TransactionBuilder::new(&mut account, Networks::testnet(), None);
+        builder
+            .fee(100u32)
+            .add_operation(op)
+            .set_timeout(30)
+            .unwrap();
+        let mut tx = server.prepare_transaction(&builder.build()).await.unwrap();
+        tx.sign(std::slice::from_ref(&keypair));
+
+        let sent = server.send_transaction(tx.clone()).await.unwrap();
+        assert_eq!(sent.status, SendTransactionStatus::Pending);
+        assert_eq!(sent.hash, hex::encode(tx.hash()));
+        let duplicate = server.send_transaction(tx).await.unwrap();
+        assert_eq!(duplicate.status, SendTransactionStatus::Duplicate);
+
+        let result = server
+            .wait_transaction(&sent.hash, Duration::from_secs(5))
+            .await
+            .unwrap();
+        assert_eq!(result.status, TransactionStatus::Success);
+        assert_eq!(result.ledger, Some(sent.latest_ledger + 1));
+
+        // The sequence number is consumed
+        let account = server.get_account(&keypair.public_key()).await.unwrap();
+        assert_eq!(account.sequence_number(), "42");
+    }
+
+    #[tokio::test]
+    async fn bad_sequence() {
+        let keypair = Keypair::random().unwrap();
+        let fake = Arc::new(FakeRpc::default());
+        fake.set_account(&keypair.public_key(), 41);
+        let server = Server::with_transport(fake.clone(), Options::default());
+
+        let mut account = server.get_account(&keypair.public_key()).await.unwrap();
+        let op = Contracts::new(CONTRACT).unwrap().call("increment", None);
+        let mut builder = TransactionBuilder::new(&mut account, Networks::testnet(), None);
+        builder
+            .fee(100u32)
+            .add_operation(op)
+            .set_timeout(30)
+            .unwrap();
+        let tx = builder.build();
+
+        fake.set_account(&keypair.public_key(), 50);
+        let sent = server.send_transaction(tx).await.unwrap();
+        assert_eq!(sent.status, SendTransactionStatus::Error);
+        assert!(matches!(
+            sent.to_error_result().unwrap().result,
+            TransactionResultResult::TxBadSeq
+        ));
+    }
+
+    #[tokio::test]
+    async fn events_and_scripted_responses() {
+        let fake = Arc::new(FakeRpc::default());
+        fake.set_ledgers_per_call(0);
+        fake.set_latest_ledger(100);
+        fake.emit_event(
+            CONTRACT,
+            vec![ScVal::Symbol("a".try_into().unwrap())],
+            ScVal::U32(1),
+        );
+        fake.advance(1);
+        fake.emit_event(
+            CONTRACT,
+            vec![ScVal::Symbol("b".try_into().unwrap())],
+            ScVal::U32(2),
+        );
+        let server = Server::with_transport(fake.clone(), Options::default());
+
+        let filter = EventFilter::new(EventType::Contract)
+            .contract(CONTRACT)
+            .topic(vec![Topic::Val(ScVal::Symbol("b".try_into().unwrap()))]);
+        let events = server
+            .get_events(Pagination::From(100), vec![filter], None)
+            .await
+            .unwrap();
+        assert_eq!(events.events.len(), 1);
+        assert_eq!(events.events[0].ledger, 101);
+        assert_eq!(events.events[0].value(), ScVal::U32(2));
+
+        fake.push_response(
+            "getLatestLedger",
+            Err(Error::RPCError {
+                code: -32603,
+                message: "internal".into(),
+                data: None,
+                request_id: None,
+            }),
+        );
+        assert!(server.get_latest_ledger().await.is_err());
+        assert_eq!(server.get_latest_ledger().await.unwrap().sequence, 101);
+        assert_eq!(fake.calls().len(), 3);
+    }
+}
@@ -1,7 +1,12 @@
+use std::sync::Arc;
+use std::sync::Mutex;
 use std::time::Duration;
 use std::vec;
 
 use crate::error::*;
+use crate::middleware::Middleware;
+use crate::middleware::OutgoingRequest;
+use crate::middleware::ReceivedResponse;
 use crate::server::*;
 use crate::soroban_rpc::GetHealthResponse;
 use crate::soroban_rpc::GetLatestLedgerResponse;
@@ -9,6 +14,7 @@ use crate::soroban_rpc::GetNetworkResponse;
 use crate::soroban_rpc::SendTransactionStatus;
 use crate::soroban_rpc::StateChangeKind;
 use crate::soroban_rpc::TransactionStatus;
+use crate::transport::StubRpc;
 use crate::EventFilter;
 use crate::Topic;
 use base64::Engine;
@@ -74,6 +80,8 @@ use wiremock::matchers::path;
 use wiremock::matchers::query_param;
 use wiremock::Mock;
 use wiremock::MockServer;
+use wiremock::Request;
+use wiremock::Respond;
 use wiremock::ResponseTemplate;
 
 #[test]
@@ -117,7 +125,7 @@ fn server_new() {
     );
     assert!(matches!(
         s5.err(),
-        Some(Error::InvalidRpc(InvalidRpcUrl::InvalidUri)),
+        Some(Error::InvalidRpc(InvalidRpcUrl::InvalidUri { .. })),
     ));
 
     let s6 = Server::new(
@@ -133,6 +141,25 @@ fn server_new() {
     ));
 }
 
+#[tokio::test]
+async fn server_with_transport() {
+    let fake = Arc::new(StubRpc::new());
+    let s = Server::with_transport(fake.clone(), Options::default());
+
+    let result = s.get_latest_ledger().await.expect("Should not fail");
+    assert_eq!(result.sequence, StubRpc::LATEST_LEDGER);
+
+    let result = s.get_transaction("abcd").await;
+    assert!(matches!(result, Err(Error::RPCError { code: -32601, .. })));
+
+    let calls = fake.calls.lock().unwrap();
+    assert_eq!(calls[0], ("getLatestLedger".to_string(), json!(null)));
+    assert_eq!(
+        calls[1],
+        ("getTransaction".to_string(), json!({"hash": "abcd"}))
+    );
+}
+
 #[tokio::test]
 async fn get_health() {
     let request = json!({"method": "getHealth"});
@@ -281,7 +308,15 @@ async fn get_account_not_found() {
 
     let (s, _m) = get_mocked_server(request, response).await;
     let result = s.get_account(address).await;
-    assert!(matches!(result, Err(Error::AccountNotFound)));
+    assert!(matches!(result, Err(Error::AccountNotFound { address: a }) if a == address));
+}
+
+#[tokio::test]
+async fn get_account_invalid_address() {
+    let s = Server::new("https://rpc", Options::default()).unwrap();
+    let err = s.get_account("GBZX").await.unwrap_err();
+    assert!(matches!(&err, Error::InvalidAddress { address, .. } if address == "GBZX"));
+    assert!(std::error::Error::source(&err).is_some());
 }
 
 #[tokio::test]
@@ -445,7 +480,7 @@ async fn get_ledger_entries() {
         let result = s.get_ledger_entries(vec![ledger_key.clone()]).await;
 
         // TODO better error should be used
-        assert!(matches!(result, Err(Error::JsonError(_))));
+        assert!(matches!(result, Err(Error::JsonError { .. })));
     }
 }
 
@@ -550,7 +585,7 @@ async fn get_contract_data() {
             .get_contract_data(address, key.clone(), Durability::Persistent)
             .await;
 
-        assert!(matches!(result, Err(Error::ContractDataNotFound)));
+        assert!(matches!(result, Err(Error::ContractDataNotFound { .. })));
     }
 }
 
@@ -904,7 +939,13 @@ async fn wait_transaction() {
         .await;
     dbg!(&txresult);
     if let Err((err, tx)) = txresult {
-        if let Error::WaitTransactionTimeout(timeout, waited) = err {
+        if let Error::WaitTransactionTimeout {
+            hash: h,
+            max_wait: timeout,
+            waited,
+        } = err
+        {
+            assert_eq!(h, hash);
             assert_eq!(timeout, test_timeout);
             assert!(waited >= timeout);
         } else {
@@ -1821,9 +1862,15 @@ async fn prepare_transaction() {
         let (s, _m) = get_mocked_server(request, response).await;
         let txresult = s.prepare_transaction(&tx).await;
 
-        if let Err(Error::RestorationRequired(min_fee, transaction_data)) = txresult {
-            assert_eq!(min_fee, 12345);
-            assert_eq!(transaction_data, soroban_data);
+        if let Err(Error::RestorationRequired {
+            hash,
+            min_resource_fee,
+            transaction_data,
+        }) = txresult
+        {
+            assert_eq!(hash, hex::encode(tx.hash()));
+            assert_eq!(min_resource_fee, 12345);
+            assert_eq!(*transaction_data, soroban_data);
         } else {
             panic!("Expecting a restore preamble")
         }
@@ -1993,43 +2040,14 @@ async fn get_events() {
      * Too much contracts
      */
     {
-        let request = json!(
-        {
-          "jsonrpc": "2.0",
-          "id": 1,
-          "method": "getEvents",
-          "params": {
-            "startLedger": 1190000,
-            "filters": [
-              {
-                "type": "contract",
-                "contractIds": [
-                  "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
-                  "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
-                  "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
-                  "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
-                  "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
-                  "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
-                ],
-              }
-            ],
-            "pagination": {
-              "limit": 2
-            }
-          }
-        }
-                );
-        let response = json!(
-            {
-              "jsonrpc": "2.0",
-              "id": 1,
-        "error":      {
-            "code": -32602,
-            "message": "filter 1 invalid: maximum 5 contract IDs per filter",
-        },
-        });
-
-        let (s, _m) = get_mocked_server(request, response).await;
+        let s = Server::new(
+            "http://localhost:8000",
+            Options {
+                allow_http: true,
+                ..Default::default()
+            },
+        )
+        .unwrap();
 
         let ledger = crate::Pagination::From(1190000);
         let contract_id = "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC";
@@ -2042,14 +2060,13 @@ async fn get_events() {
             .contract(contract_id)];
         let result = s.get_events(ledger, filters, 2).await;
 
-        if let Err(Error::RPCError { code, message }) = result {
-            assert_eq!(code, -32602);
+        if let Err(e @ Error::EventFilterLimit { .. }) = result {
             assert_eq!(
-                message,
-                "filter 1 invalid: maximum 5 contract IDs per filter"
+                e.to_string(),
+                "EventFilterLimit: 6 contract IDs per filter, the maximum is 5"
             );
         } else {
-            panic!("Expecting an RPCError")
+            panic!("Expecting an EventFilterLimit")
         }
     }
     // TODO more tests
@@ -2218,7 +2235,7 @@ async fn request_airdrop() {
         );
         let (s, _m) = get_mocked_server(request, response).await;
         let result = s.request_airdrop(account_id).await;
-        assert!(matches!(result.err(), Some(Error::AccountNotFound)));
+        assert!(matches!(result.err(), Some(Error::AccountNotFound { .. })));
     }
     /*
      * Found url from testnet
@@ -2254,7 +2271,6 @@ async fn request_airdrop() {
         let request = json!(
             {
                 "jsonrpc": "2.0",
-                "id": 1,
                 "method": "getLedgerEntries",
                 "params": { "keys": [value] },
         }
@@ -2275,11 +2291,10 @@ async fn request_airdrop() {
             }
         }
         );
-        let response = ResponseTemplate::new(200).set_body_json(response);
         Mock::given(method("POST"))
             .and(path("/"))
             .and(matchers::body_partial_json(request))
-            .respond_with(response)
+            .respond_with(EchoId::new(response))
             .expect(1..)
             .mount(&mock_server)
             .await;
@@ -2573,19 +2588,250 @@ async fn get_ledgers() {
     }
 }
 
+#[tokio::test]
+async fn batch() {
+    let request = json!([
+        {"jsonrpc": "2.0", "id": 1, "method": "getLatestLedger"},
+        {"jsonrpc": "2.0", "id": 2, "method": "getTransaction", "params": {"hash": "abcd"}},
+    ]);
+    // Responses are not in the requests order
+    let response = json!([
+        {
+            "jsonrpc": "2.0",
+            "id": 2,
+            "error": {"code": -32602, "message": "invalid hash"}
+        },
+        {
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {
+                "id": "c73c5eac58a441d4eb733c35253ae85f783e018f7be5ef974258fed067aabb36",
+                "protocolVersion": 20,
+                "sequence": 2539605
+            }
+        },
+    ]);
+    let (s, _m) = get_mocked_server(request, response).await;
+
+    let mut batch = s.batch();
+    let latest = batch.get_latest_ledger();
+    let tx = batch.get_transaction("abcd");
+    assert_eq!(batch.len(), 2);
+
+    let mut results = batch.send().await.expect("Should not fail");
+    let latest = results.take(latest).expect("Should not fail");
+    assert_eq!(latest.sequence, 2539605);
+    assert!(matches!(
+        results.take(tx),
+        Err(Error::RPCError { code: -32602, .. })
+    ));
+}
+
+#[tokio::test]
+async fn middleware() {
+    #[derive(Default)]
+    struct Recorder {
+        received: Arc<Mutex<Vec<(String, u16, String)>>>,
+    }
+
+    #[crate::transport::async_trait]
+    impl Middleware for Recorder {
+        async fn before_send(&self, request: &mut OutgoingRequest) -> Result<(), Error> {
+            request
+                .headers
+                .insert("authorization", "Bearer token".parse().unwrap());
+            Ok(())
+        }
+
+        async fn after_receive(&self, response: &ReceivedResponse<'_>) {
+            self.received.lock().unwrap().push((
+                response.method.to_string(),
+                response.status.as_u16(),
+                response.text.to_string(),
+            ));
+        }
+    }
+
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(matchers::header("authorization", "Bearer token"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {"passphrase": "Test", "protocolVersion": 22}
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let recorder = Recorder::default();
+    let received = recorder.received.clone();
+    let mut options = Options {
+        allow_http: true,
+        ..Default::default()
+    };
+    options.middleware.push(recorder);
+    let s = Server::new(&mock_server.uri(), options).expect("Configuration should not fail");
+
+    let network = s.get_network().await.expect("Should not fail");
+    assert_eq!(network.passphrase.as_deref(), Some("Test"));
+
+    let received = received.lock().unwrap();
+    assert_eq!(received.len(), 1);
+    assert_eq!(received[0].0, "getNetwork");
+    assert_eq!(received[0].1, 200);
+    assert!(received[0].2.contains("protocolVersion"));
+}
+
+#[tokio::test]
+async fn http_client() {
+    let mock_server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(matchers::header("x-proxy-token", "secret"))
+        .and(matchers::header("x-client-name", "rs-soroban-client"))
+        .respond_with(EchoId::new(json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "result": {"passphrase": "Test", "protocolVersion": 22}
+        })))
+        .expect(1)
+        .mount(&mock_server)
+        .await;
+
+    let mut headers = reqwest::header::HeaderMap::new();
+    headers.insert("x-proxy-token", "secret".parse().unwrap());
+    let http_client = reqwest::Client::builder()
+        .default_headers(headers)
+        .build()
+        .unwrap();
+    let s = Server::new(
+        &mock_server.uri(),
+        Options {
+            allow_http: true,
+            http_client: Some(http_client),
+            ..Default::default()
+        },
+    )
+    .expect("Configuration should not fail");
+
+    let network = s.get_network().await.expect("Should not fail");
+    assert_eq!(network.passphrase.as_deref(), Some("Test"));
+}
+
+#[tokio::test]
+async fn tracing_span() {
+    let logs = Arc::new(Mutex::new(Vec::new()));
+    let writer = logs.clone();
+    let subscriber = tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::DEBUG)
+        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+        .with_ansi(false)
+        .with_writer(move || LogWriter(writer.clone()))
+        .finish();
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let request = json!({"method": "getHealth"});
+    let response = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "error": {"code": -32603, "message": "internal error"}
+    });
+    let (s, m) = get_mocked_server(request, response).await;
+    assert!(s.get_health().await.is_err());
+
+    let logs = String::from_utf8(logs.lock().unwrap().clone()).unwrap();
+    let close = logs
+        .lines()
+        .find(|l| l.contains("rpc{") && !l.contains("rpc_request{") && l.contains("close"))
+        .expect("No span closed");
+    assert!(close.contains(r#"method="getHealth""#));
+    assert!(close.contains(&format!(r#"endpoint="{}/""#, m.uri())));
+    assert!(close.contains("request_id=1"));
+    assert!(close.contains("outcome=\"rpc_error\""));
+    assert!(close.contains("rpc_code=-32603"));
+    assert!(close.contains("duration_ms="));
+}
+
+struct LogWriter(Arc<Mutex<Vec<u8>>>);
+
+impl std::io::Write for LogWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// Reply a JSON-RPC `response` with the id of the request
+pub(crate) struct EchoId {
+    response: serde_json::Value,
+    delay: Duration,
+}
+
+impl EchoId {
+    pub(crate) fn new(response: serde_json::Value) -> Self {
+        EchoId {
+            response,
+            delay: Duration::ZERO,
+        }
+    }
+
+    pub(crate) fn delay(self, delay: Duration) -> Self {
+        EchoId { delay, ..self }
+    }
+}
+
+impl Respond for EchoId {
+    fn respond(&self, request: &Request) -> ResponseTemplate {
+        let mut response = self.response.clone();
+        // Batch responses are not changed
+        if let (Some(response), Ok(request)) = (
+            response.as_object_mut(),
+            serde_json::from_slice::<serde_json::Value>(&request.body),
+        ) {
+            response.insert("id".to_string(), request["id"].clone());
+        }
+        ResponseTemplate::new(200)
+            .set_body_json(response)
+            .set_delay(self.delay)
+    }
+}
+
+// Contract event of a getEvents response, in `ledger` with the event `id`
+pub(crate) fn event_json(ledger: u32, id: &str) -> serde_json::Value {
+    json!({
+        "type": "contract",
+        "ledger": ledger,
+        "ledgerClosedAt": "2024-04-19T06:19:00Z",
+        "contractId": "CDLZFC3SYJYDZT7K67VZ75HPJVIEUVNIXF47ZG2FB2RMQQVU2HHGCYSC",
+        "id": id,
+        "txHash": "05870e35fc94e5424f72d125959760b5f60631d91452bde2d11126fb5044e35d",
+        "inSuccessfulContractCall": true,
+        "topic": [],
+        "value": "AAAAAQ=="
+    })
+}
+
 // Create a Server that will reply `response` for a json `request` partially matching
 async fn get_mocked_server(
-    request: serde_json::Value,
+    mut request: serde_json::Value,
     response: serde_json::Value,
 ) -> (Server, MockServer) {
     let mock_server = MockServer::start().await;
     let server_url = mock_server.uri();
 
-    let response = ResponseTemplate::new(200).set_body_json(response);
+    // Request ids are chosen by the client
+    if let Some(request) = request.as_object_mut() {
+        request.remove("id");
+    }
+
     Mock::given(method("POST"))
         .and(path("/"))
         .and(matchers::body_partial_json(request))
-        .respond_with(response)
+        .respond_with(EchoId::new(response))
         .expect(1..)
         .mount(&mock_server)
         .await;
@@ -0,0 +1,27 @@
+// Timer used by the client, `tokio` on native targets and the browser timers on wasm32
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(not(target_arch = "wasm32"))]
+pub use tokio::time::{sleep, Instant};
+
+#[cfg(target_arch = "wasm32")]
+pub use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+#[cfg(target_arch = "wasm32")]
+pub async fn sleep(duration: std::time::Duration) {
+    gloo_timers::future::sleep(duration).await
+}
+
+/// Run `future` for at most `duration`, `None` if it did not complete in time
+#[cfg(target_arch = "wasm32")]
+pub async fn timeout<F: std::future::Future>(
+    duration: std::time::Duration,
+    future: F,
+) -> Option<F::Output> {
+    use futures::future::{select, Either};
+    match select(Box::pin(future), Box::pin(sleep(duration))).await {
+        Either::Left((output, _)) => Some(output),
+        Either::Right(_) => None,
+    }
+}
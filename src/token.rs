@@ -0,0 +1,495 @@
+use stellar_baselib::xdr::ScVal;
+
+use crate::error::Error;
+use crate::events::{EventDecoder, FromContractEvent, FromScVal};
+
+/// Event emitted by a [SEP-41] token, such as a Stellar Asset Contract
+///
+/// Addresses are strkeys. `asset` is the last topic of the events emitted by a Stellar Asset
+/// Contract, `native` or `CODE:ISSUER`. Both the events emitted before and after [CAP-67] are
+/// decoded, `admin` is `None` for the events which no longer have it.
+///
+/// # Example
+/// ```rust
+/// # use soroban_client::error::Error;
+/// # use soroban_client::soroban_rpc::EventResponse;
+/// use soroban_client::events::FromContractEvent;
+/// use soroban_client::token::TokenEvent;
+///
+/// fn transferred(events: &[EventResponse]) -> Result<i128, Error> {
+///     let mut total = 0;
+///     for event in events {
+///         if let TokenEvent::Transfer { amount, .. } = TokenEvent::from_event_response(event)? {
+///             total += amount;
+///         }
+///     }
+///     Ok(total)
+/// }
+/// ```
+///
+/// [SEP-41]: https://github.com/stellar/stellar-protocol/blob/master/ecosystem/sep-0041.md
+/// [CAP-67]: https://github.com/stellar/stellar-protocol/blob/master/core/cap-0067.md
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TokenEvent {
+    /// `amount` moved from `from` to `to`
+    Transfer {
+        /// Sender
+        from: String,
+        /// Recipient
+        to: String,
+        /// Amount transferred
+        amount: i128,
+        /// (optional) Muxed id of the recipient
+        to_muxed_id: Option<MuxedId>,
+        /// (optional) Asset of a Stellar Asset Contract
+        asset: Option<String>,
+    },
+    /// `amount` created for `to`
+    Mint {
+        /// (optional) Admin of the token, before CAP-67
+        admin: Option<String>,
+        /// Recipient
+        to: String,
+        /// Amount minted
+        amount: i128,
+        /// (optional) Muxed id of the recipient
+        to_muxed_id: Option<MuxedId>,
+        /// (optional) Asset of a Stellar Asset Contract
+        asset: Option<String>,
+    },
+    /// `amount` destroyed by `from`
+    Burn {
+        /// Owner of the burned amount
+        from: String,
+        /// Amount burned
+        amount: i128,
+        /// (optional) Asset of a Stellar Asset Contract
+        asset: Option<String>,
+    },
+    /// `amount` taken back from `from` by the admin
+    Clawback {
+        /// (optional) Admin of the token, before CAP-67
+        admin: Option<String>,
+        /// Owner of the amount clawed back
+        from: String,
+        /// Amount clawed back
+        amount: i128,
+        /// (optional) Asset of a Stellar Asset Contract
+        asset: Option<String>,
+    },
+    /// `spender` allowed to spend `amount` of `from`
+    Approve {
+        /// Owner of the allowance
+        from: String,
+        /// Spender of the allowance
+        spender: String,
+        /// Amount allowed
+        amount: i128,
+        /// Last ledger of the allowance
+        live_until_ledger: u32,
+        /// (optional) Asset of a Stellar Asset Contract
+        asset: Option<String>,
+    },
+    /// Admin of the token replaced
+    SetAdmin {
+        /// Previous admin
+        admin: String,
+        /// New admin
+        new_admin: String,
+        /// (optional) Asset of a Stellar Asset Contract
+        asset: Option<String>,
+    },
+    /// Authorization of `id` changed
+    SetAuthorized {
+        /// (optional) Admin of the token, before CAP-67
+        admin: Option<String>,
+        /// Address authorized or deauthorized
+        id: String,
+        /// Whether `id` is authorized
+        authorize: bool,
+        /// (optional) Asset of a Stellar Asset Contract
+        asset: Option<String>,
+    },
+}
+
+/// Muxed id of the recipient of a [TokenEvent], added by CAP-67
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MuxedId {
+    /// Id of a muxed account or memo id
+    Id(u64),
+    /// Memo text
+    Text(String),
+    /// Memo hash
+    Hash([u8; 32]),
+}
+
+impl FromScVal for MuxedId {
+    fn from_sc_val(val: &ScVal) -> Result<Self, String> {
+        match val {
+            ScVal::U64(id) => Ok(MuxedId::Id(*id)),
+            ScVal::String(s) => Ok(MuxedId::Text(s.0.to_utf8_string_lossy())),
+            ScVal::Bytes(_) => FromScVal::from_sc_val(val).map(MuxedId::Hash),
+            val => Err(format!(
+                "expected U64, String or Bytes, found {}",
+                val.discriminant().name()
+            )),
+        }
+    }
+}
+
+const NAMES: [&str; 7] = [
+    "transfer",
+    "mint",
+    "burn",
+    "clawback",
+    "approve",
+    "set_admin",
+    "set_authorized",
+];
+
+impl TokenEvent {
+    /// Whether the first topic is the name of a token event
+    pub fn is_token_event(topics: &[ScVal]) -> bool {
+        match topics.first() {
+            Some(ScVal::Symbol(s)) => NAMES.iter().any(|name| s.as_slice() == name.as_bytes()),
+            _ => false,
+        }
+    }
+
+    /// Name of the event, its first topic
+    pub fn name(&self) -> &'static str {
+        let index = match self {
+            TokenEvent::Transfer { .. } => 0,
+            TokenEvent::Mint { .. } => 1,
+            TokenEvent::Burn { .. } => 2,
+            TokenEvent::Clawback { .. } => 3,
+            TokenEvent::Approve { .. } => 4,
+            TokenEvent::SetAdmin { .. } => 5,
+            TokenEvent::SetAuthorized { .. } => 6,
+        };
+        NAMES[index]
+    }
+}
+
+impl FromContractEvent for TokenEvent {
+    fn from_contract_event(topics: &[ScVal], value: &ScVal) -> Result<Self, Error> {
+        let decoder = EventDecoder::new("TokenEvent", topics, value);
+        let invalid = |reason| Error::InvalidEvent {
+            target: "TokenEvent",
+            reason,
+        };
+        let name = match decoder.topic(0)? {
+            ScVal::Symbol(s) => s.0.to_utf8_string_lossy(),
+            val => {
+                return Err(invalid(format!(
+                    "topic 0: expected Symbol, found {}",
+                    val.discriminant().name()
+                )))
+            }
+        };
+        let asset = match topics.last() {
+            Some(ScVal::String(s)) if topics.len() > 2 => Some(s.0.to_utf8_string_lossy()),
+            _ => None,
+        };
+        // CAP-67 removed the admin topic, before it was followed by another address
+        let admin_and_target = || -> Result<(Option<String>, String), Error> {
+            match topics.get(2) {
+                Some(ScVal::Address(_)) => Ok((Some(decoder.topic(1)?), decoder.topic(2)?)),
+                _ => Ok((None, decoder.topic(1)?)),
+            }
+        };
+        // CAP-67 made the value a map when the recipient is muxed
+        let muxed_amount = || -> Result<(i128, Option<MuxedId>), Error> {
+            match value {
+                ScVal::Map(_) => Ok((
+                    decoder.value_entry("amount")?,
+                    decoder.value_entry("to_muxed_id")?,
+                )),
+                _ => Ok((decoder.value()?, None)),
+            }
+        };
+
+        let event = match name.as_str() {
+            "transfer" => {
+                let (amount, to_muxed_id) = muxed_amount()?;
+                TokenEvent::Transfer {
+                    from: decoder.topic(1)?,
+                    to: decoder.topic(2)?,
+                    amount,
+                    to_muxed_id,
+                    asset,
+                }
+            }
+            "mint" => {
+                let (admin, to) = admin_and_target()?;
+                let (amount, to_muxed_id) = muxed_amount()?;
+                TokenEvent::Mint {
+                    admin,
+                    to,
+                    amount,
+                    to_muxed_id,
+                    asset,
+                }
+            }
+            "burn" => TokenEvent::Burn {
+                from: decoder.topic(1)?,
+                amount: decoder.value()?,
+                asset,
+            },
+            "clawback" => {
+                let (admin, from) = admin_and_target()?;
+                TokenEvent::Clawback {
+                    admin,
+                    from,
+                    amount: decoder.value()?,
+                    asset,
+                }
+            }
+            "approve" => {
+                let (amount, live_until_ledger) = decoder.value()?;
+                TokenEvent::Approve {
+                    from: decoder.topic(1)?,
+                    spender: decoder.topic(2)?,
+                    amount,
+                    live_until_ledger,
+                    asset,
+                }
+            }
+            "set_admin" => TokenEvent::SetAdmin {
+                admin: decoder.topic(1)?,
+                new_admin: decoder.value()?,
+                asset,
+            },
+            "set_authorized" => {
+                let (admin, id) = admin_and_target()?;
+                TokenEvent::SetAuthorized {
+                    admin,
+                    id,
+                    authorize: decoder.value()?,
+                    asset,
+                }
+            }
+            name => return Err(invalid(format!("topic 0: `{name}` is not a token event"))),
+        };
+        Ok(event)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+    use stellar_baselib::xdr::{
+        AccountId, ContractEvent, ContractEventBody, ContractEventType, ContractEventV0,
+        ContractId, ExtensionPoint, Hash, Int128Parts, Limits, PublicKey, ScAddress, ScMap,
+        ScMapEntry, ScString, Uint256, WriteXdr,
+    };
+
+    use super::*;
+    use crate::soroban_rpc::TransactionDetails;
+
+    fn symbol(s: &str) -> ScVal {
+        ScVal::Symbol(s.try_into().unwrap())
+    }
+
+    fn string(s: &str) -> ScVal {
+        ScVal::String(ScString(s.try_into().unwrap()))
+    }
+
+    fn account(byte: u8) -> ScAddress {
+        ScAddress::Account(AccountId(PublicKey::PublicKeyTypeEd25519(Uint256(
+            [byte; 32],
+        ))))
+    }
+
+    fn amount(lo: u64) -> ScVal {
+        ScVal::I128(Int128Parts { hi: 0, lo })
+    }
+
+    fn decode(topics: Vec<ScVal>, value: ScVal) -> Result<TokenEvent, Error> {
+        TokenEvent::from_contract_event(&topics, &value)
+    }
+
+    #[test]
+    fn transfer() {
+        let (from, to) = (account(1), account(2));
+        let topics = vec![
+            symbol("transfer"),
+            ScVal::Address(from.clone()),
+            ScVal::Address(to.clone()),
+            string("native"),
+        ];
+        assert_eq!(
+            decode(topics.clone(), amount(100)).unwrap(),
+            TokenEvent::Transfer {
+                from: from.to_string(),
+                to: to.to_string(),
+                amount: 100,
+                to_muxed_id: None,
+                asset: Some("native".to_string()),
+            }
+        );
+
+        // CAP-67, to a muxed account
+        let entry = |key: &str, val| ScMapEntry {
+            key: symbol(key),
+            val,
+        };
+        let value = ScVal::Map(Some(ScMap(
+            vec![
+                entry("amount", amount(100)),
+                entry("to_muxed_id", ScVal::U64(7)),
+            ]
+            .try_into()
+            .unwrap(),
+        )));
+        let event = decode(topics, value).unwrap();
+        assert!(matches!(
+            event,
+            TokenEvent::Transfer {
+                amount: 100,
+                to_muxed_id: Some(MuxedId::Id(7)),
+                ..
+            }
+        ));
+        assert_eq!(event.name(), "transfer");
+    }
+
+    #[test]
+    fn admin_topic() {
+        let (admin, to) = (account(1), account(2));
+        let mint = decode(
+            vec![
+                symbol("mint"),
+                ScVal::Address(admin.clone()),
+                ScVal::Address(to.clone()),
+                string("USDC:GA5ZSEJYB37JRC5AVCIA5MOP4RHTM335X2KGX3IHOJAPP5RE34K4KZVN"),
+            ],
+            amount(5),
+        )
+        .unwrap();
+        assert_eq!(
+            mint,
+            TokenEvent::Mint {
+                admin: Some(admin.to_string()),
+                to: to.to_string(),
+                amount: 5,
+                to_muxed_id: None,
+                asset: Some(
+                    "USDC:GA5ZSEJYB37JRC5AVCIA5MOP4RHTM335X2KGX3IHOJAPP5RE34K4KZVN".to_string()
+                ),
+            }
+        );
+
+        // CAP-67 removed the admin
+        let clawback = decode(
+            vec![symbol("clawback"), ScVal::Address(to.clone())],
+            amount(3),
+        )
+        .unwrap();
+        assert_eq!(
+            clawback,
+            TokenEvent::Clawback {
+                admin: None,
+                from: to.to_string(),
+                amount: 3,
+                asset: None,
+            }
+        );
+
+        let set_authorized = decode(
+            vec![
+                symbol("set_authorized"),
+                ScVal::Address(to.clone()),
+                string("native"),
+            ],
+            ScVal::Bool(false),
+        )
+        .unwrap();
+        assert!(matches!(
+            set_authorized,
+            TokenEvent::SetAuthorized {
+                admin: None,
+                authorize: false,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn approve_and_errors() {
+        let (from, spender) = (account(1), account(2));
+        let topics = vec![
+            symbol("approve"),
+            ScVal::Address(from.clone()),
+            ScVal::Address(spender.clone()),
+        ];
+        let value = ScVal::Vec(Some(vec![amount(10), ScVal::U32(2000)].try_into().unwrap()));
+        assert_eq!(
+            decode(topics.clone(), value).unwrap(),
+            TokenEvent::Approve {
+                from: from.to_string(),
+                spender: spender.to_string(),
+                amount: 10,
+                live_until_ledger: 2000,
+                asset: None,
+            }
+        );
+
+        let error = |topics: Vec<ScVal>, value| decode(topics, value).unwrap_err().to_string();
+        assert_eq!(
+            error(topics, amount(10)),
+            "InvalidEvent: cannot decode TokenEvent, value: expected Vec, found I128"
+        );
+        assert_eq!(
+            error(vec![symbol("init")], ScVal::Void),
+            "InvalidEvent: cannot decode TokenEvent, topic 0: `init` is not a token event"
+        );
+        assert!(!TokenEvent::is_token_event(&[symbol("init")]));
+    }
+
+    #[test]
+    fn transaction_token_events() {
+        let contract = ScAddress::Contract(ContractId(Hash([9; 32])));
+        let event = |topics: Vec<ScVal>, data| {
+            ContractEvent {
+                ext: ExtensionPoint::V0,
+                contract_id: Some(ContractId(Hash([9; 32]))),
+                type_: ContractEventType::Contract,
+                body: ContractEventBody::V0(ContractEventV0 {
+                    topics: topics.try_into().unwrap(),
+                    data,
+                }),
+            }
+            .to_xdr_base64(Limits::none())
+            .unwrap()
+        };
+        let burn = event(vec![symbol("burn"), ScVal::Address(account(1))], amount(4));
+        let other = event(vec![symbol("init")], ScVal::Void);
+        // NFT transfer, named like a token event with a token id instead of an amount
+        let nft = event(
+            vec![
+                symbol("transfer"),
+                ScVal::Address(account(1)),
+                ScVal::Address(account(2)),
+            ],
+            ScVal::U32(7),
+        );
+        let details: TransactionDetails = serde_json::from_value(json!({
+            "status": "SUCCESS",
+            "events": { "contractEventsXdr": [[other, nft, burn]] }
+        }))
+        .unwrap();
+
+        assert_eq!(
+            details.try_to_token_events().unwrap().unwrap(),
+            [(
+                contract.to_string(),
+                TokenEvent::Burn {
+                    from: account(1).to_string(),
+                    amount: 4,
+                    asset: None,
+                }
+            )]
+        );
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{error::Error, soroban_rpc::*};
+use crate::{
+    error::{Cause, Error},
+    soroban_rpc::*,
+};
 use stellar_baselib::operation::Operation;
 pub use stellar_baselib::{
     account::Account,
@@ -25,32 +28,47 @@ pub fn assemble_transaction(
     }
 
     if let Some(error) = simulation.error {
-        return Err(Error::SimulationFailed(error));
+        return Err(Error::SimulationFailed {
+            hash: hex::encode(tx.hash()),
+            error,
+        });
     }
 
-    if let Some((min_fee, restore)) = simulation.to_restore_transaction_data() {
-        return Err(Error::RestorationRequired(min_fee, restore));
+    if let Some((min_resource_fee, transaction_data)) =
+        simulation.try_to_restore_transaction_data()?
+    {
+        return Err(Error::RestorationRequired {
+            hash: hex::encode(tx.hash()),
+            min_resource_fee,
+            transaction_data: Box::new(transaction_data),
+        });
     }
 
-    let sim_auth = if let Some((_, a)) = simulation.to_result() {
-        Some(a)
-    } else {
-        None
-    };
-
-    let min_resource_fee = simulation
-        .min_resource_fee
-        .as_ref()
-        .unwrap()
-        .parse::<u32>()
-        .unwrap_or(0);
-
-    let soroban_tx_data = simulation
-        .to_transaction_data()
-        .expect("No transaction data");
+    let sim_auth = simulation.try_to_result()?.map(|(_, a)| a);
+
+    let min_resource_fee = simulation.try_min_resource_fee()?;
+    let fee = u32::try_from(min_resource_fee).map_err(|e| Error::InvalidResponse {
+        field: "minResourceFee",
+        source: e.into(),
+    })?;
+    let fee = tx
+        .fee
+        .checked_add(fee)
+        .ok_or_else(|| Error::InvalidResponse {
+            field: "minResourceFee",
+            source: Cause(format!("{min_resource_fee} overflows the fee")).into(),
+        })?;
+
+    let soroban_tx_data =
+        simulation
+            .try_to_transaction_data()?
+            .ok_or_else(|| Error::InvalidResponse {
+                field: "transactionData",
+                source: Cause("missing".to_string()).into(),
+            })?;
 
     let mut ntx = tx.clone();
-    ntx.fee += min_resource_fee;
+    ntx.fee = fee;
     ntx.soroban_data = Some(soroban_tx_data);
 
     // Process the operation
@@ -67,7 +85,11 @@ pub fn assemble_transaction(
             };
             ntx.operations = Some(vec![Operation::new()
                 .invoke_host_function(host_function, checked_auth)
-                .map_err(|_| Error::TransactionError)?]);
+                .map_err(|e| Error::TransactionError {
+                    operation: "building the invoke host function operation",
+                    hash: hex::encode(tx.hash()),
+                    source: Cause(format!("{e:?}")),
+                })?]);
         }
     }
 
@@ -97,6 +119,7 @@ mod test {
     use serde_json::json;
     use stellar_baselib::{
         account::{Account, AccountBehavior},
+        transaction::TransactionBehavior,
         transaction_builder::{TransactionBuilder, TransactionBuilderBehavior},
         xdr::{
             AccountId, ContractId, CreateAccountOp, Hash, HostFunction, InvokeContractArgs,
@@ -226,7 +249,95 @@ mod test {
         .unwrap();
 
         let r = assemble_transaction(&tx, simulation);
-        assert!(matches!(r, Err(Error::SimulationFailed(_))));
+        assert!(matches!(
+            r,
+            Err(Error::SimulationFailed { hash, error })
+                if hash == hex::encode(tx.hash()) && error == "This is an error"
+        ));
+    }
+
+    #[test]
+    fn malformed_simulation() {
+        let mut source_account = Account::new(
+            "GAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAAWHF",
+            "0",
+        )
+        .unwrap();
+        let network = "Network for tests";
+
+        let op = Operation {
+            source_account: None,
+            body: OperationBody::InvokeHostFunction(InvokeHostFunctionOp {
+                host_function: HostFunction::InvokeContract(InvokeContractArgs {
+                    contract_address: ScAddress::Contract(ContractId(Hash([0; 32]))),
+                    function_name: ScSymbol::from(StringM::from_str("test").unwrap()),
+                    args: VecM::<ScVal>::try_from(Vec::new()).unwrap(),
+                }),
+                auth: VecM::<SorobanAuthorizationEntry>::try_from(Vec::new()).unwrap(),
+            }),
+        };
+
+        let mut builder = TransactionBuilder::new(&mut source_account, network, None);
+        builder.fee(1000u32).set_timeout(30).unwrap();
+        builder.add_operation(op);
+        let tx = builder.build();
+        let data = "AAAAAAAAAAIAAAAGAAAAAcwD/nT9D7Dc2LxRdab+2vEUF8B+XoN7mQW21oxPT8ALAAAAFAAAAAEAAAAHy8vNUZ8vyZ2ybPHW0XbSrRtP7gEWsJ6zDzcfY9P8z88AAAABAAAABgAAAAHMA/50/Q+w3Ni8UXWm/trxFBfAfl6De5kFttaMT0/ACwAAABAAAAABAAAAAgAAAA8AAAAHQ291bnRlcgAAAAASAAAAAAAAAAAg4dbAxsGAGICfBG3iT2cKGYQ6hK4sJWzZ6or1C5v6GAAAAAEAHfKyAAAFiAAAAIgAAAAAAAAAAw==";
+        let simulation = |value: serde_json::Value| -> SimulateTransactionResponse {
+            serde_json::from_value(value).unwrap()
+        };
+
+        let s = simulation(json!({"transactionData": data, "latestLedger": 1}));
+        let r = assemble_transaction(&tx, s);
+        assert!(matches!(
+            r,
+            Err(Error::InvalidResponse {
+                field: "minResourceFee",
+                ..
+            })
+        ));
+
+        let s = simulation(
+            json!({"minResourceFee": "abc", "transactionData": data, "latestLedger": 1}),
+        );
+        let r = assemble_transaction(&tx, s);
+        // The parsing error is kept as the source
+        assert!(matches!(
+            r,
+            Err(Error::InvalidResponse {
+                field: "minResourceFee",
+                source,
+            }) if source.is::<std::num::ParseIntError>()
+        ));
+
+        let s = simulation(json!({"minResourceFee": "100", "latestLedger": 1}));
+        let r = assemble_transaction(&tx, s);
+        assert!(matches!(
+            r,
+            Err(Error::InvalidResponse {
+                field: "transactionData",
+                ..
+            })
+        ));
+
+        let s = simulation(json!({
+            "minResourceFee": "100",
+            "transactionData": data,
+            "results": [{"auth": [], "xdr": "not xdr"}],
+            "latestLedger": 1
+        }));
+        assert!(matches!(s.try_to_result(), Err(Error::XdrError { .. })));
+        let r = assemble_transaction(&tx, s);
+        assert!(matches!(r, Err(Error::XdrError { .. })));
+
+        let s = simulation(json!({"results": [], "latestLedger": 1}));
+        assert!(matches!(s.try_to_result(), Ok(None)));
+        assert!(s.to_result().is_none());
+
+        let s = simulation(
+            json!({"minResourceFee": "100", "transactionData": data, "latestLedger": 1}),
+        );
+        let r = assemble_transaction(&tx, s).unwrap();
+        assert_eq!(r.fee, 1100);
     }
 
     #[test]
@@ -0,0 +1,156 @@
+use std::sync::Arc;
+
+use serde_json::Value;
+
+use crate::error::Error;
+pub use crate::jsonrpc::JsonRpc;
+/// Re-export of the attribute required to implement [RpcTransport]
+pub use async_trait::async_trait;
+
+/// Transport used by [Server](crate::Server) to reach the RPC
+///
+/// A transport sends a JSON-RPC `method` with its `params` and returns the `result` member of the
+/// response. Errors returned by the RPC must be mapped to [Error::RPCError].
+///
+/// The default transport is [JsonRpc], built on `reqwest`. Implement this trait to use another
+/// HTTP stack, an in-memory fake or a proxy, and pass it to
+/// [Server::with_transport](crate::Server::with_transport).
+///
+/// On wasm32 the futures are not `Send`, implement the trait with `#[async_trait(?Send)]`.
+///
+/// # Example
+/// ```rust
+/// # use soroban_client::*;
+/// # use soroban_client::error::Error;
+/// # use soroban_client::transport::RpcTransport;
+/// use serde_json::{json, Value};
+///
+/// struct Fake;
+///
+/// #[soroban_client::transport::async_trait]
+/// impl RpcTransport for Fake {
+///     async fn call(&self, method: &str, _params: Value) -> Result<Value, Error> {
+///         match method {
+///             "getHealth" => Ok(json!({
+///                 "status": "healthy",
+///                 "latestLedger": 100,
+///                 "oldestLedger": 1,
+///                 "ledgerRetentionWindow": 100
+///             })),
+///             _ => Err(Error::RPCError {
+///                 code: -32601,
+///                 message: "method not found".into(),
+///                 data: None,
+///                 request_id: None,
+///             }),
+///         }
+///     }
+/// }
+///
+/// # async fn run() -> Result<(), Error> {
+/// let server = Server::with_transport(Fake, Options::default());
+/// let health = server.get_health().await?;
+/// # Ok(()) }
+/// ```
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait RpcTransport: Send + Sync {
+    /// Call the RPC `method` with `params` and return the `result` of the response
+    async fn call(&self, method: &str, params: Value) -> Result<Value, Error>;
+
+    /// Call several `(method, params)` at once and return one result per call, in the same order
+    ///
+    /// The outer error is returned when the whole batch failed. The default implementation
+    /// calls each method in sequence with [RpcTransport::call].
+    async fn call_batch(
+        &self,
+        calls: Vec<(String, Value)>,
+    ) -> Result<Vec<Result<Value, Error>>, Error> {
+        let mut results = Vec::with_capacity(calls.len());
+        for (method, params) in calls {
+            results.push(self.call(&method, params).await);
+        }
+        Ok(results)
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T: RpcTransport + ?Sized> RpcTransport for Arc<T> {
+    async fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+        (**self).call(method, params).await
+    }
+
+    async fn call_batch(
+        &self,
+        calls: Vec<(String, Value)>,
+    ) -> Result<Vec<Result<Value, Error>>, Error> {
+        (**self).call_batch(calls).await
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl<T: RpcTransport + ?Sized> RpcTransport for Box<T> {
+    async fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+        (**self).call(method, params).await
+    }
+
+    async fn call_batch(
+        &self,
+        calls: Vec<(String, Value)>,
+    ) -> Result<Vec<Result<Value, Error>>, Error> {
+        (**self).call_batch(calls).await
+    }
+}
+
+/// Fake RPC answering `getLatestLedger` with the sequence [StubRpc::LATEST_LEDGER], and the
+/// methods added with [StubRpc::with]. The other methods fail with "method not found".
+#[cfg(test)]
+pub(crate) struct StubRpc {
+    results: Vec<(&'static str, Value)>,
+    pub(crate) calls: std::sync::Mutex<Vec<(String, Value)>>,
+}
+
+#[cfg(test)]
+impl StubRpc {
+    pub(crate) const LATEST_LEDGER: u32 = 2539605;
+
+    pub(crate) fn new() -> Self {
+        let latest_ledger = serde_json::json!({
+            "id": "c73c5eac58a441d4eb733c35253ae85f783e018f7be5ef974258fed067aabb36",
+            "protocolVersion": 20,
+            "sequence": Self::LATEST_LEDGER
+        });
+        StubRpc {
+            results: vec![("getLatestLedger", latest_ledger)],
+            calls: Default::default(),
+        }
+    }
+
+    #[cfg_attr(not(feature = "blocking"), allow(dead_code))]
+    pub(crate) fn with(mut self, method: &'static str, result: Value) -> Self {
+        self.results.push((method, result));
+        self
+    }
+}
+
+#[cfg(test)]
+#[async_trait]
+impl RpcTransport for StubRpc {
+    async fn call(&self, method: &str, params: Value) -> Result<Value, Error> {
+        self.calls
+            .lock()
+            .unwrap()
+            .push((method.to_string(), params));
+        match self.results.iter().find(|(m, _)| *m == method) {
+            Some((_, result)) => Ok(result.clone()),
+            None => Err(Error::RPCError {
+                code: -32601,
+                message: "method not found".to_string(),
+                data: None,
+                request_id: None,
+            }),
+        }
+    }
+}